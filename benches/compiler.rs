@@ -0,0 +1,32 @@
+use advent_of_code_2017::intcode::io::NoIo;
+use advent_of_code_2017::intcode::{Computer, Instruction};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+fn read_program(file: &str) -> Option<Vec<Instruction>> {
+    let input = std::fs::read_to_string(file).ok()?;
+    input.lines().map(|line| line.parse().ok()).collect()
+}
+
+fn compiler(c: &mut Criterion) {
+    let file = "inputs/day23.txt";
+    let instructions = match read_program(file) {
+        Some(instructions) => instructions,
+        None => {
+            eprintln!("Skipping day23: could not read {}.", file);
+            return;
+        }
+    };
+
+    let mut group = c.benchmark_group("day23");
+    group.bench_function("interpreted", |b| {
+        b.iter(|| Computer::with_io(instructions.clone(), NoIo).run_until_halt(None))
+    });
+    group.bench_function("compiled", |b| {
+        let compiled = Computer::with_io(instructions.clone(), NoIo).compile();
+        b.iter(|| compiled.run(&mut Computer::with_io(instructions.clone(), NoIo), None))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, compiler);
+criterion_main!(benches);
@@ -0,0 +1,104 @@
+use advent_of_code_2017::intcode::io::NoIo;
+use advent_of_code_2017::intcode::{Computer, Instruction, Parameter, Semantics, Value};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::collections::HashMap;
+
+/// The register storage `Computer` used before, to compare against.
+#[derive(Debug, Default)]
+struct HashMapComputer {
+    registers: HashMap<char, Value>,
+    instruction_pointer: usize,
+    last_played: Value,
+}
+
+impl HashMapComputer {
+    fn get_value(&self, parameter: Parameter) -> Value {
+        match parameter {
+            Parameter::Value(v) => v,
+            Parameter::Register(r) => self.registers.get(&r.name()).copied().unwrap_or_default(),
+        }
+    }
+
+    fn jump(&mut self, offset: Value) {
+        self.instruction_pointer = (self.instruction_pointer as Value + offset) as usize;
+    }
+
+    /// Runs with the "sound" semantics, until a frequency is recovered, the
+    /// program stops, or it reaches an instruction this computer never had.
+    fn run(&mut self, instructions: &[Instruction]) -> Option<Value> {
+        while let Some(&instruction) = instructions.get(self.instruction_pointer) {
+            match instruction {
+                Instruction::Send(p) => self.last_played = self.get_value(p),
+                Instruction::Set(r, p) => {
+                    self.registers.insert(r.name(), self.get_value(p));
+                }
+                Instruction::Add(r, p) => {
+                    *self.registers.entry(r.name()).or_default() += self.get_value(p)
+                }
+                Instruction::Sub(r, p) => {
+                    *self.registers.entry(r.name()).or_default() -= self.get_value(p)
+                }
+                Instruction::Multiply(r, p) => {
+                    *self.registers.entry(r.name()).or_default() *= self.get_value(p)
+                }
+                Instruction::Modulo(r, p) => {
+                    *self.registers.entry(r.name()).or_default() %= self.get_value(p)
+                }
+                Instruction::Receive(r) => {
+                    if self.get_value(Parameter::Register(r)) != 0 {
+                        return Some(self.last_played);
+                    }
+                }
+                Instruction::JumpGreaterThanZero(x, p) => {
+                    if self.get_value(x) > 0 {
+                        self.jump(self.get_value(p));
+                        continue;
+                    }
+                }
+                Instruction::JumpIfNotZero(x, p) => {
+                    if self.get_value(x) != 0 {
+                        self.jump(self.get_value(p));
+                        continue;
+                    }
+                }
+                Instruction::Fused(_) | Instruction::Extended(_, _, _) => return None,
+            }
+            self.instruction_pointer += 1;
+        }
+
+        None
+    }
+}
+
+fn read_program(file: &str) -> Option<Vec<Instruction>> {
+    let input = std::fs::read_to_string(file).ok()?;
+    input.lines().map(|line| line.parse().ok()).collect()
+}
+
+fn registers(c: &mut Criterion) {
+    for (day, file) in [("day18", "inputs/day18.txt"), ("day23", "inputs/day23.txt")] {
+        let instructions = match read_program(file) {
+            Some(instructions) => instructions,
+            None => {
+                eprintln!("Skipping {}: could not read {}.", day, file);
+                continue;
+            }
+        };
+
+        let mut group = c.benchmark_group(day);
+        group.bench_function("array", |b| {
+            b.iter(|| {
+                let mut computer = Computer::with_io(instructions.clone(), NoIo);
+                computer.set_semantics(Semantics::Sound);
+                computer.recover()
+            })
+        });
+        group.bench_function("hashmap", |b| {
+            b.iter(|| HashMapComputer::default().run(&instructions))
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, registers);
+criterion_main!(benches);
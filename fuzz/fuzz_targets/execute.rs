@@ -0,0 +1,56 @@
+#![no_main]
+
+use advent_of_code_2017::intcode::analysis::ControlFlowGraph;
+use advent_of_code_2017::intcode::assembler::assemble_with;
+use advent_of_code_2017::intcode::decompiler::Decompiler;
+use advent_of_code_2017::intcode::dialect::Dialect;
+use advent_of_code_2017::intcode::interval::{summarize_loops, IntervalAnalysis, State};
+use advent_of_code_2017::intcode::io::Queue;
+use advent_of_code_2017::intcode::optimizer::optimize;
+use advent_of_code_2017::intcode::{Computer, ExecError};
+use libfuzzer_sys::fuzz_target;
+
+const STEP_LIMIT: usize = 10_000;
+
+fuzz_target!(|data: &[u8]| {
+    let source = match std::str::from_utf8(data) {
+        Ok(source) => source,
+        Err(_) => return,
+    };
+    // Every opcode there is, extra ones included.
+    let instructions = match assemble_with(source, &Dialect::permissive()) {
+        Ok(instructions) => instructions,
+        Err(_) => return,
+    };
+
+    // The compiled program is checked against the interpreter.
+    let computer = Computer::with_io(instructions.clone(), Queue::new([3, -1, 0, 7]));
+    let mut interpreted = computer.clone();
+    let mut compiled = computer;
+    let halt = interpreted.run_until_halt(Some(STEP_LIMIT));
+    assert_eq!(
+        compiled.compile().run(&mut compiled, Some(STEP_LIMIT)),
+        halt
+    );
+    assert_eq!(compiled.io(), interpreted.io());
+
+    // So is the optimized program, which must do exactly what the original
+    // does, in fewer steps. With the same fuel, the original may run out where
+    // the optimized program does not, so only runs that both finish compare.
+    let optimized = optimize(&instructions);
+    let mut fast = Computer::with_io(optimized.clone(), Queue::new([3, -1, 0, 7]));
+    let fast_halt = fast.run_until_halt(Some(STEP_LIMIT));
+    let exhausted = Some(ExecError::StepLimitExhausted);
+    if halt.error != exhausted && fast_halt.error != exhausted {
+        assert_eq!(fast_halt.error, halt.error);
+        assert_eq!(fast_halt.instruction_pointer, halt.instruction_pointer);
+        assert_eq!(fast_halt.registers, halt.registers);
+        assert_eq!(fast.io(), interpreted.io());
+        assert!(fast_halt.steps <= halt.steps);
+    }
+
+    ControlFlowGraph::new(&optimized).to_dot();
+    Decompiler::new(&optimized).decompile();
+    IntervalAnalysis::new(&optimized, State::default()).listing();
+    summarize_loops(&optimized);
+});
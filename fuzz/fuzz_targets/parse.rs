@@ -0,0 +1,33 @@
+#![no_main]
+
+use advent_of_code_2017::intcode::assembler::assemble_with;
+use advent_of_code_2017::intcode::dialect::{Dialect, EXTRAS};
+use advent_of_code_2017::intcode::disassembler::disassemble;
+use advent_of_code_2017::intcode::Instruction;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let source = match std::str::from_utf8(data) {
+        Ok(source) => source,
+        Err(_) => return,
+    };
+    let dialect = EXTRAS
+        .iter()
+        .fold(Dialect::permissive(), |dialect, opcode| {
+            dialect.with(opcode)
+        });
+
+    for line in source.lines() {
+        // Whatever parses prints back to the same instruction.
+        if let Ok(instruction) = line.parse::<Instruction>() {
+            assert_eq!(instruction.to_string().parse(), Ok(instruction));
+        }
+        if let Ok(instruction) = dialect.parse(line) {
+            assert_eq!(dialect.parse(&instruction.to_string()), Ok(instruction));
+        }
+    }
+
+    if let Ok(instructions) = assemble_with(source, &dialect) {
+        disassemble(&instructions);
+    }
+});
@@ -46,7 +46,7 @@ pub struct PixelGrid {
 impl PixelGrid {
     pub fn enhance(&mut self) {
         // Oh, this is horrible.
-        let (step, mut grid) = if self.grid.len() % 2 == 0 {
+        let (step, mut grid) = if self.grid.len().is_multiple_of(2) {
             (
                 2,
                 vec![vec![Pixel::default(); 3 * self.grid.len() / 2]; 3 * self.grid.len() / 2],
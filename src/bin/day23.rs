@@ -1,20 +1,21 @@
-use advent_of_code_2017::intcode::{Computer, Instruction};
-use primal::is_prime;
-use std::sync::mpsc::channel;
+use advent_of_code_2017::intcode::dialect::Dialect;
+use advent_of_code_2017::intcode::io::NoIo;
+use advent_of_code_2017::intcode::optimizer::optimize;
+use advent_of_code_2017::intcode::{Computer, Instruction, Register};
 
 const FILE: &str = "inputs/day23.txt";
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let input = std::fs::read_to_string(FILE)?;
+    let dialect = Dialect::coprocessor();
     let instructions = input
         .lines()
-        .map(|line| line.parse())
+        .map(|line| dialect.parse(line))
         .collect::<Result<Vec<Instruction>, _>>()?;
 
     // If you run the program (your puzzle input), how many times is the mul
     // instruction invoked?
-    let (s0, r0) = channel();
-    let part1 = Computer::new(instructions, 0, s0, r0).debug_mode();
+    let part1 = Computer::with_io(instructions.clone(), NoIo).debug_mode();
     println!("Part 1: {}", part1);
 
     // Reading the program:
@@ -23,11 +24,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     //   if CURRENT is composite, increment register h
     // It is incredibly inefficient, since it iterates 2 nested loops (2 to
     // CURRENT) for every check (not even short-circuiting!).
-    // We'll do it a different way instead.
-    let part2 = (93 * 100 + 100_000..=93 * 100 + 100_000 + 17_000)
-        .step_by(17)
-        .filter(|&n| !is_prime(n))
-        .count();
+    // The optimizer recognizes those loops, and replaces them with a proper
+    // composite check.
+    let mut computer = Computer::with_io(optimize(&instructions), NoIo);
+    computer.set_register("a".parse::<Register>()?, 1);
+    computer.run();
+    let part2 = computer.register("h".parse::<Register>()?);
     println!("Part 2: {}", part2);
 
     Ok(())
@@ -0,0 +1,60 @@
+use advent_of_code_2017::intcode::assembler::assemble;
+use advent_of_code_2017::intcode::interval::{summarize_loops, Interval, IntervalAnalysis, State};
+use advent_of_code_2017::intcode::optimizer::optimize;
+use advent_of_code_2017::intcode::{Register, Value};
+
+/// `VALUE` or `MIN..MAX`.
+fn parse_interval(s: &str) -> Result<Interval, String> {
+    let parse = |v: &str| v.parse::<Value>().map_err(|e| format!("{}: {}", e, s));
+    match s.split_once("..") {
+        Some((min, max)) => {
+            Interval::new(parse(min)?, parse(max)?).ok_or_else(|| format!("Empty interval: {}", s))
+        }
+        None => Ok(Interval::constant(parse(s)?)),
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: intcode_analyze [--optimize] PROGRAM [REGISTER=VALUE|REGISTER=MIN..MAX]...";
+    let mut args = std::env::args().skip(1).peekable();
+    let optimized = args.next_if(|arg| arg == "--optimize").is_some();
+    let file = args.next().ok_or(usage)?;
+    let input = std::fs::read_to_string(&file)?;
+    let mut instructions = assemble(&input).map_err(|diagnostics| {
+        diagnostics
+            .iter()
+            .map(|diagnostic| format!("{}:{}", file, diagnostic))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })?;
+    if optimized {
+        instructions = optimize(&instructions);
+    }
+
+    let mut initial = State::default();
+    for arg in args {
+        let (register, interval) = arg
+            .split_once('=')
+            .ok_or_else(|| format!("Expected REGISTER=RANGE, got: {}", arg))?;
+        initial[register.parse::<Register>()?] = parse_interval(interval)?;
+    }
+
+    let analysis = IntervalAnalysis::new(&instructions, initial);
+    print!("{}", analysis.listing());
+
+    println!();
+    for summary in summarize_loops(&instructions) {
+        print!("{}", summary);
+    }
+
+    let unreachable = analysis.unreachable();
+    if !unreachable.is_empty() {
+        println!("\nUnreachable: {:?}", unreachable);
+    }
+    let non_terminating = analysis.non_terminating();
+    if !non_terminating.is_empty() {
+        println!("\nNever halts from: {:?}", non_terminating);
+    }
+
+    Ok(())
+}
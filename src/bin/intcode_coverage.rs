@@ -0,0 +1,86 @@
+use advent_of_code_2017::intcode::assembler::{assemble_with, line_numbers};
+use advent_of_code_2017::intcode::budget::Outcome;
+use advent_of_code_2017::intcode::coverage::Coverage;
+use advent_of_code_2017::intcode::dialect::Dialect;
+use advent_of_code_2017::intcode::io::Queue;
+use advent_of_code_2017::intcode::{Computer, ParseError, Register, Semantics, Value};
+
+/// How many steps each run may take, by default.
+const BUDGET: usize = 100_000_000;
+
+/// `REGISTER=VALUE[,REGISTER=VALUE]...`
+fn parse_run(s: &str) -> Result<Vec<(Register, Value)>, String> {
+    s.split(',')
+        .map(|assignment| {
+            let (register, value) = assignment
+                .split_once('=')
+                .ok_or_else(|| format!("Expected REGISTER=VALUE, got: {}", assignment))?;
+            let value = value
+                .parse()
+                .map_err(|_| format!("Invalid number: {}", value))?;
+            let register = register.parse().map_err(|e: ParseError| e.to_string())?;
+            Ok((register, value))
+        })
+        .collect()
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: intcode_coverage [--lcov] [--sound] [--dialect DIALECT] \
+                 [--budget STEPS] PROGRAM [REGISTER=VALUE[,REGISTER=VALUE]...]...";
+    let mut args = std::env::args().skip(1);
+    let mut lcov = false;
+    let mut semantics = Semantics::Duet;
+    let mut dialect = Dialect::default();
+    let mut budget = BUDGET;
+    let file = loop {
+        match args.next().ok_or(usage)?.as_str() {
+            "--lcov" => lcov = true,
+            "--sound" => semantics = Semantics::Sound,
+            "--dialect" => dialect = args.next().ok_or(usage)?.parse()?,
+            "--budget" => budget = args.next().ok_or(usage)?.parse()?,
+            file => break file.to_string(),
+        }
+    };
+    let input = std::fs::read_to_string(&file)?;
+    let instructions = assemble_with(&input, &dialect).map_err(|diagnostics| {
+        diagnostics
+            .iter()
+            .map(|diagnostic| format!("{}:{}", file, diagnostic))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })?;
+
+    // Every argument is a separate run, with its own initial registers.
+    let mut runs = args
+        .map(|arg| parse_run(&arg))
+        .collect::<Result<Vec<_>, _>>()?;
+    if runs.is_empty() {
+        runs.push(Vec::new());
+    }
+
+    let mut coverage = Coverage::new(&instructions);
+    for (idx, run) in runs.into_iter().enumerate() {
+        let mut computer = Computer::with_io(instructions.clone(), Queue::default());
+        computer.set_semantics(semantics);
+        for (register, value) in run {
+            computer.set_register(register, value);
+        }
+        let (profile, outcome) = computer.profile_with_budget(budget);
+        if outcome == Outcome::OutOfFuel {
+            eprintln!(
+                "Warning: run {} was stopped after {} steps, so its coverage is partial.",
+                idx + 1,
+                budget
+            );
+        }
+        coverage.add(&profile);
+    }
+
+    if lcov {
+        print!("{}", coverage.lcov(&file, &line_numbers(&input)));
+    } else {
+        print!("{}", coverage.listing(&instructions));
+    }
+
+    Ok(())
+}
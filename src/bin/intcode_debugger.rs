@@ -0,0 +1,258 @@
+use advent_of_code_2017::intcode::assembler::assemble;
+use advent_of_code_2017::intcode::debugger::{Debugger, Event};
+use advent_of_code_2017::intcode::io::Queue;
+use advent_of_code_2017::intcode::snapshot::Snapshot;
+use advent_of_code_2017::intcode::{Computer, Register, Value};
+use std::io::{BufRead, Write};
+
+const HELP: &str = "\
+Commands:
+  s, step [N]        execute N instructions (default 1)
+  c, continue        run until a breakpoint, watchpoint or halt
+  history [N|all|off]
+                     record the last N instructions (default 100000), so
+                     they can be undone; off by default
+  back [N]           undo N instructions (default 1)
+  rewind R           undo until right before the last write to register R
+  b, break PC        set a breakpoint
+  d, delete PC       remove a breakpoint
+  w, watch R         set a watchpoint on register R
+  u, unwatch R       remove a watchpoint
+  r, registers       show the registers and instruction pointer
+  l, list            list the program
+  i, input V         queue a value to be received
+  o, output          show (and clear) the values sent so far
+  set R V            set register R to V
+  save FILE          write a snapshot of the machine to FILE
+  load FILE          restore the machine from a snapshot in FILE
+  h, help            show this message
+  q, quit            exit";
+
+/// How many instructions `history` keeps by default.
+const HISTORY_LIMIT: Value = 100_000;
+
+fn print_event(debugger: &Debugger<Queue>, event: Event) {
+    match event {
+        Event::Stepped => (),
+        Event::Breakpoint(pc) => println!("Breakpoint at {}.", pc),
+        Event::Watchpoint { register, old, new } => {
+            println!("Watchpoint on {}: {} -> {}", register, old, new)
+        }
+        Event::Halted => println!("Program halted."),
+        Event::Error(error) => println!("Error: {}", error),
+    }
+
+    let computer = debugger.computer();
+    if let Some(instruction) = computer.current_instruction() {
+        println!("{:>4}: {}", computer.instruction_pointer(), instruction);
+    }
+}
+
+fn print_registers(debugger: &Debugger<Queue>) {
+    let computer = debugger.computer();
+    for (register, value) in computer.registers().non_zero() {
+        println!("{} = {}", register, value);
+    }
+    println!("pc = {}", computer.instruction_pointer());
+}
+
+fn list(debugger: &Debugger<Queue>) {
+    let computer = debugger.computer();
+    let breakpoints: Vec<usize> = debugger.breakpoints().collect();
+    for (pc, instruction) in computer.instructions().iter().enumerate() {
+        let marker = if pc == computer.instruction_pointer() {
+            '>'
+        } else if breakpoints.contains(&pc) {
+            '*'
+        } else {
+            ' '
+        };
+        println!("{}{:>4}: {}", marker, pc, instruction);
+    }
+}
+
+fn execute(debugger: &mut Debugger<Queue>, line: &str) -> Result<bool, String> {
+    let mut words = line.split_whitespace();
+    let command = match words.next() {
+        Some(command) => command,
+        None => return Ok(true),
+    };
+    let argument = words.next();
+
+    let register = |argument: Option<&str>| {
+        argument
+            .ok_or_else(|| "Missing register.".to_string())?
+            .parse::<Register>()
+            .map_err(|e| e.to_string())
+    };
+    let number = |argument: Option<&str>| {
+        argument
+            .ok_or_else(|| "Missing number.".to_string())?
+            .parse::<Value>()
+            .map_err(|e| e.to_string())
+    };
+
+    match command {
+        "s" | "step" => {
+            let count = argument.map_or(Ok(1), |_| number(argument))?;
+            for _ in 0..count {
+                match debugger.step() {
+                    Event::Stepped => (),
+                    event => {
+                        print_event(debugger, event);
+                        return Ok(true);
+                    }
+                }
+            }
+            print_event(debugger, Event::Stepped);
+        }
+        "history" => match argument {
+            Some("off") => {
+                debugger.computer_mut().disable_history();
+                println!("History disabled.");
+            }
+            Some("all") => {
+                debugger.computer_mut().enable_history();
+                println!("Recording every instruction.");
+            }
+            _ => {
+                let limit = argument.map_or(Ok(HISTORY_LIMIT), |_| number(argument))?;
+                let limit = usize::try_from(limit).map_err(|e| e.to_string())?;
+                debugger.computer_mut().enable_bounded_history(limit);
+                println!("Recording the last {} instructions.", limit);
+            }
+        },
+        "back" => {
+            if debugger.computer().history_limit().is_none() {
+                return Err("The history is disabled, see `history`.".to_string());
+            }
+            let count = argument.map_or(Ok(1), |_| number(argument))?;
+            for _ in 0..count {
+                if !debugger
+                    .computer_mut()
+                    .step_back()
+                    .map_err(|e| e.to_string())?
+                {
+                    println!("Reached the beginning of the history.");
+                    break;
+                }
+            }
+            print_event(debugger, Event::Stepped);
+        }
+        "rewind" => {
+            if debugger.computer().history_limit().is_none() {
+                return Err("The history is disabled, see `history`.".to_string());
+            }
+            let r = register(argument)?;
+            match debugger
+                .computer_mut()
+                .rewind_to_write(r)
+                .map_err(|e| e.to_string())?
+            {
+                Some(_) => print_event(debugger, Event::Stepped),
+                None => return Err(format!("No write to {} in the history.", r)),
+            }
+        }
+        "c" | "continue" => {
+            let event = debugger.resume();
+            print_event(debugger, event);
+        }
+        "b" | "break" => {
+            let pc = number(argument)?;
+            let pc = usize::try_from(pc).map_err(|e| e.to_string())?;
+            debugger.add_breakpoint(pc);
+        }
+        "d" | "delete" => {
+            let pc = number(argument)?;
+            let pc = usize::try_from(pc).map_err(|e| e.to_string())?;
+            if !debugger.remove_breakpoint(pc) {
+                return Err(format!("No breakpoint at {}.", pc));
+            }
+        }
+        "w" | "watch" => {
+            debugger.add_watchpoint(register(argument)?);
+        }
+        "u" | "unwatch" => {
+            let r = register(argument)?;
+            if !debugger.remove_watchpoint(r) {
+                return Err(format!("No watchpoint on {}.", r));
+            }
+        }
+        "r" | "registers" => print_registers(debugger),
+        "l" | "list" => list(debugger),
+        "i" | "input" => {
+            let value = number(argument)?;
+            debugger.computer_mut().io_mut().input.push_back(value);
+        }
+        "o" | "output" => {
+            let output: Vec<Value> = debugger.computer_mut().io_mut().output.drain(..).collect();
+            println!("{:?}", output);
+        }
+        "set" => {
+            let r = register(argument)?;
+            let value = number(words.next())?;
+            debugger.computer_mut().set_register(r, value);
+        }
+        "save" => {
+            let file = argument.ok_or("Missing file.")?;
+            let snapshot = debugger
+                .computer_mut()
+                .snapshot()
+                .map_err(|e| e.to_string())?;
+            std::fs::write(file, snapshot.to_string()).map_err(|e| e.to_string())?;
+        }
+        "load" => {
+            let file = argument.ok_or("Missing file.")?;
+            let snapshot: Snapshot = std::fs::read_to_string(file)
+                .map_err(|e| e.to_string())?
+                .parse()?;
+            debugger
+                .computer_mut()
+                .restore(&snapshot)
+                .map_err(|e| e.to_string())?;
+            print_event(debugger, Event::Stepped);
+        }
+        "h" | "help" => println!("{}", HELP),
+        "q" | "quit" => return Ok(false),
+        _ => return Err(format!("Unknown command: {}", command)),
+    }
+
+    Ok(true)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let file = std::env::args()
+        .nth(1)
+        .ok_or("Usage: intcode_debugger PROGRAM")?;
+    let input = std::fs::read_to_string(&file)?;
+    let instructions = assemble(&input).map_err(|diagnostics| {
+        diagnostics
+            .iter()
+            .map(|diagnostic| format!("{}:{}", file, diagnostic))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })?;
+
+    let mut debugger = Debugger::new(Computer::with_io(instructions, Queue::default()));
+    print_event(&debugger, Event::Stepped);
+
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("(debug) ");
+        std::io::stdout().flush()?;
+
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+
+        match execute(&mut debugger, &line) {
+            Ok(true) => (),
+            Ok(false) => break,
+            Err(error) => println!("{}", error),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use advent_of_code_2017::intcode::assembler::assemble;
+use advent_of_code_2017::intcode::decompiler::Decompiler;
+use advent_of_code_2017::intcode::Register;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let file = args
+        .next()
+        .ok_or("Usage: intcode_decompile PROGRAM [REGISTER=NAME]...")?;
+    let input = std::fs::read_to_string(&file)?;
+    let instructions = assemble(&input).map_err(|diagnostics| {
+        diagnostics
+            .iter()
+            .map(|diagnostic| format!("{}:{}", file, diagnostic))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })?;
+
+    let mut decompiler = Decompiler::new(&instructions);
+    for arg in args {
+        let (register, name) = arg
+            .split_once('=')
+            .ok_or_else(|| format!("Expected REGISTER=NAME, got: {}", arg))?;
+        let register: Register = register.parse()?;
+        decompiler = decompiler.name(register, name);
+    }
+
+    print!("{}", decompiler.decompile());
+
+    Ok(())
+}
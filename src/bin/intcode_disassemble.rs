@@ -0,0 +1,34 @@
+use advent_of_code_2017::intcode::assembler::assemble_with;
+use advent_of_code_2017::intcode::dialect::Dialect;
+use advent_of_code_2017::intcode::disassembler::disassemble;
+use advent_of_code_2017::intcode::optimizer::optimize;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: intcode_disassemble [--optimize] [--dialect DIALECT] PROGRAM";
+    let mut args = std::env::args().skip(1);
+    let mut optimized = false;
+    let mut dialect = Dialect::default();
+    let file = loop {
+        match args.next().ok_or(usage)?.as_str() {
+            "--optimize" => optimized = true,
+            "--dialect" => dialect = args.next().ok_or(usage)?.parse()?,
+            file => break file.to_string(),
+        }
+    };
+    let input = std::fs::read_to_string(&file)?;
+    let mut instructions = assemble_with(&input, &dialect).map_err(|diagnostics| {
+        diagnostics
+            .iter()
+            .map(|diagnostic| format!("{}:{}", file, diagnostic))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })?;
+
+    if optimized {
+        instructions = optimize(&instructions);
+    }
+
+    print!("{}", disassemble(&instructions));
+
+    Ok(())
+}
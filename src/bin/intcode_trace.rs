@@ -0,0 +1,62 @@
+use advent_of_code_2017::intcode::assembler::assemble_with;
+use advent_of_code_2017::intcode::dialect::Dialect;
+use advent_of_code_2017::intcode::io::Queue;
+use advent_of_code_2017::intcode::optimizer::optimize;
+use advent_of_code_2017::intcode::{Computer, Register, Semantics, Value};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: intcode_trace [--optimize] [--compiled] [--sound] [--dialect DIALECT] \
+                 [--limit STEPS] PROGRAM [REGISTER=VALUE]...";
+    let mut args = std::env::args().skip(1);
+    let mut optimized = false;
+    let mut compiled = false;
+    let mut semantics = Semantics::Duet;
+    let mut dialect = Dialect::default();
+    let mut limit = None;
+    let file = loop {
+        match args.next().ok_or(usage)?.as_str() {
+            "--optimize" => optimized = true,
+            "--compiled" => compiled = true,
+            "--sound" => semantics = Semantics::Sound,
+            "--dialect" => dialect = args.next().ok_or(usage)?.parse()?,
+            "--limit" => limit = Some(args.next().ok_or(usage)?.parse()?),
+            file => break file.to_string(),
+        }
+    };
+    let input = std::fs::read_to_string(&file)?;
+    let mut instructions = assemble_with(&input, &dialect).map_err(|diagnostics| {
+        diagnostics
+            .iter()
+            .map(|diagnostic| format!("{}:{}", file, diagnostic))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })?;
+    if optimized {
+        instructions = optimize(&instructions);
+    }
+
+    let mut computer = Computer::with_io(instructions, Queue::default());
+    computer.set_semantics(semantics);
+    for arg in args {
+        let (register, value) = arg
+            .split_once('=')
+            .ok_or_else(|| format!("Expected REGISTER=VALUE, got: {}", arg))?;
+        computer.set_register(register.parse::<Register>()?, value.parse::<Value>()?);
+    }
+
+    computer.enable_tracing();
+    let halt = if compiled {
+        computer.compile().run(&mut computer, limit)
+    } else {
+        computer.run_until_halt(limit)
+    };
+
+    for record in computer.trace() {
+        println!("{}", record);
+    }
+    if let Some(error) = halt.error {
+        eprintln!("Stopped at {}: {}", halt.instruction_pointer, error);
+    }
+
+    Ok(())
+}
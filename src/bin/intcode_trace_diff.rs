@@ -0,0 +1,38 @@
+use advent_of_code_2017::intcode::trace::{diff, diff_effects, Record};
+
+fn read_trace(file: &str) -> Result<Vec<Record>, Box<dyn std::error::Error>> {
+    let input = std::fs::read_to_string(file)?;
+    let records = input
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| {
+            line.parse()
+                .map_err(|e| format!("{}:{}: {}", file, idx + 1, e))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(records)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: intcode_trace_diff [--effects] LEFT RIGHT";
+    let mut args = std::env::args().skip(1).peekable();
+    let effects = args.next_if(|arg| arg == "--effects").is_some();
+    let left = read_trace(&args.next().ok_or(usage)?)?;
+    let right = read_trace(&args.next().ok_or(usage)?)?;
+
+    let divergence = if effects {
+        diff_effects(&left, &right)
+    } else {
+        diff(&left, &right)
+    };
+    match divergence {
+        Some(divergence) => {
+            print!("{}", divergence);
+            std::process::exit(1);
+        }
+        None if effects => println!("Same effects: {} and {} records.", left.len(), right.len()),
+        None => println!("Identical traces: {} records.", left.len()),
+    }
+
+    Ok(())
+}
@@ -1,8 +1,37 @@
-use std::collections::HashMap;
+use std::fmt::Display;
 use std::str::FromStr;
-use std::sync::mpsc::{Receiver, RecvError, Sender};
+use std::sync::mpsc::{Receiver, Sender};
+
+pub mod analysis;
+pub mod arithmetic;
+pub mod assembler;
+pub mod asynchronous;
+pub mod budget;
+pub mod compiler;
+pub mod coverage;
+pub mod debugger;
+pub mod decompiler;
+pub mod dialect;
+pub mod disassembler;
+pub mod history;
+pub mod interval;
+pub mod io;
+pub mod network;
+pub mod optimizer;
+pub mod profiler;
+pub mod registers;
+pub mod scheduler;
+pub mod snapshot;
+pub mod trace;
+
+use arithmetic::{ArithmeticMode, ModuloMode, Operation};
+use budget::CancelHandle;
+use dialect::{Behavior, Opcode};
+use io::{Channel, Io, IoError};
+use optimizer::Fused;
+pub use registers::{Register, Registers};
+use trace::Tracer;
 
-pub type Register = char;
 pub type Value = i64;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -12,18 +41,26 @@ pub enum Parameter {
 }
 
 impl FromStr for Parameter {
-    type Err = String;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        s.trim().parse::<Value>().map_or_else(
-            |_| {
-                s.chars()
-                    .next()
-                    .map(Parameter::Register)
-                    .ok_or_else(|| s.to_string())
-            },
-            |v| Ok(Parameter::Value(v)),
-        )
+        let s = s.trim();
+        match s.parse::<Value>() {
+            Ok(v) => Ok(Parameter::Value(v)),
+            Err(_) => s
+                .parse()
+                .map(Parameter::Register)
+                .map_err(|_| ParseError::InvalidParameter(s.to_string())),
+        }
+    }
+}
+
+impl Display for Parameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Parameter::Value(v) => write!(f, "{}", v),
+            Parameter::Register(r) => write!(f, "{}", r),
+        }
     }
 }
 
@@ -38,159 +75,474 @@ pub enum Instruction {
     Receive(Register),
     JumpGreaterThanZero(Parameter, Parameter),
     JumpIfNotZero(Parameter, Parameter),
+    /// Only produced by `optimizer::optimize`.
+    Fused(Fused),
+    /// An opcode from a `dialect::Dialect`. Arithmetic opcodes always have a
+    /// register as their first operand, and the second operand of those that
+    /// take only one is unused.
+    Extended(&'static Opcode, Parameter, Parameter),
+}
+
+impl Instruction {
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Send(_) => "snd",
+            Instruction::Set(_, _) => "set",
+            Instruction::Add(_, _) => "add",
+            Instruction::Sub(_, _) => "sub",
+            Instruction::Multiply(_, _) => "mul",
+            Instruction::Modulo(_, _) => "mod",
+            Instruction::Receive(_) => "rcv",
+            Instruction::JumpGreaterThanZero(_, _) => "jgz",
+            Instruction::JumpIfNotZero(_, _) => "jnz",
+            Instruction::Fused(fused) => fused.mnemonic(),
+            Instruction::Extended(opcode, _, _) => opcode.mnemonic,
+        }
+    }
+
+    /// The register this writes to, if any.
+    pub fn destination(&self) -> Option<Register> {
+        match *self {
+            Instruction::Set(r, _)
+            | Instruction::Add(r, _)
+            | Instruction::Sub(r, _)
+            | Instruction::Multiply(r, _)
+            | Instruction::Modulo(r, _)
+            | Instruction::Receive(r) => Some(r),
+            Instruction::Extended(opcode, Parameter::Register(r), _)
+                if matches!(
+                    opcode.behavior,
+                    Behavior::Arithmetic(_) | Behavior::Division(_)
+                ) =>
+            {
+                Some(r)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_jump(&self) -> bool {
+        self.jump().is_some()
+    }
+
+    /// The condition and offset of a jump.
+    pub fn jump(&self) -> Option<(Parameter, Parameter)> {
+        match *self {
+            Instruction::JumpGreaterThanZero(x, y) | Instruction::JumpIfNotZero(x, y) => {
+                Some((x, y))
+            }
+            Instruction::Extended(opcode, x, y) if matches!(opcode.behavior, Behavior::Jump(_)) => {
+                Some((x, y))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether a jump is taken when its condition has the value `x`.
+    pub fn is_taken(&self, x: Value) -> Option<bool> {
+        match self {
+            Instruction::JumpGreaterThanZero(_, _) => Some(x > 0),
+            Instruction::JumpIfNotZero(_, _) => Some(x != 0),
+            Instruction::Extended(opcode, _, _) => match opcode.behavior {
+                Behavior::Jump(condition) => Some(condition(x)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+fn word_at<'a>(words: &[&'a str], idx: usize) -> Result<&'a str, (usize, ParseError)> {
+    words
+        .get(idx)
+        .copied()
+        .ok_or((words.len(), ParseError::MissingParameter(idx)))
+}
+
+fn register_at(words: &[&str], idx: usize) -> Result<Register, (usize, ParseError)> {
+    word_at(words, idx)?.parse().map_err(|error| (idx, error))
+}
+
+fn parameter_at(words: &[&str], idx: usize) -> Result<Parameter, (usize, ParseError)> {
+    word_at(words, idx)?.parse().map_err(|error| (idx, error))
+}
+
+impl Instruction {
+    /// Parse an instruction already split into words. On failure, also
+    /// returns the index of the offending word (the number of words, if one
+    /// is missing).
+    pub fn parse_words(words: &[&str]) -> Result<Self, (usize, ParseError)> {
+        let register = |idx: usize| register_at(words, idx);
+        let parameter = |idx: usize| parameter_at(words, idx);
+
+        let op = *words.first().ok_or((0, ParseError::MissingOperation))?;
+
+        let (instruction, nbr_words) = match op {
+            "snd" => (Instruction::Send(parameter(1)?), 2),
+            "set" => (Instruction::Set(register(1)?, parameter(2)?), 3),
+            "add" => (Instruction::Add(register(1)?, parameter(2)?), 3),
+            "sub" => (Instruction::Sub(register(1)?, parameter(2)?), 3),
+            "mul" => (Instruction::Multiply(register(1)?, parameter(2)?), 3),
+            "mod" => (Instruction::Modulo(register(1)?, parameter(2)?), 3),
+            "rcv" => (Instruction::Receive(register(1)?), 2),
+            "jgz" => (
+                Instruction::JumpGreaterThanZero(parameter(1)?, parameter(2)?),
+                3,
+            ),
+            "jnz" => (Instruction::JumpIfNotZero(parameter(1)?, parameter(2)?), 3),
+            _ => return Err((0, ParseError::UnknownOperation(op.to_string()))),
+        };
+
+        match words.get(nbr_words) {
+            Some(extra) => Err((
+                nbr_words,
+                ParseError::UnexpectedParameter(extra.to_string()),
+            )),
+            None => Ok(instruction),
+        }
+    }
+}
+
+/// The same text `FromStr` accepts, such as `jnz a -3`. Superinstructions
+/// have no source form, so they are shown with their own mnemonic and will
+/// not parse back.
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mnemonic = self.mnemonic();
+        match self {
+            Instruction::Send(x) => write!(f, "{} {}", mnemonic, x),
+            Instruction::Set(r, p)
+            | Instruction::Add(r, p)
+            | Instruction::Sub(r, p)
+            | Instruction::Multiply(r, p)
+            | Instruction::Modulo(r, p) => write!(f, "{} {} {}", mnemonic, r, p),
+            Instruction::Receive(r) => write!(f, "{} {}", mnemonic, r),
+            Instruction::JumpGreaterThanZero(x, y) | Instruction::JumpIfNotZero(x, y) => {
+                write!(f, "{} {} {}", mnemonic, x, y)
+            }
+            Instruction::Fused(fused) => write!(f, "{}", fused),
+            Instruction::Extended(opcode, x, y) => match opcode.arity() {
+                1 => write!(f, "{} {}", mnemonic, x),
+                _ => write!(f, "{} {} {}", mnemonic, x, y),
+            },
+        }
+    }
 }
 
 impl FromStr for Instruction {
-    type Err = String;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut iter = s.split_whitespace();
-
-        let op = iter.next().ok_or("Missing operation.")?;
-        let first = iter.next().ok_or("Missing first parameter.")?;
-        let second = iter.next();
-
-        match op {
-            "snd" => first.parse().map(Instruction::Send),
-            "set" => {
-                let register = first.chars().next().expect(
-                    "There should be at least one character, since we used `split_whitespace`.",
-                );
-                second
-                    .ok_or_else(|| s.to_string())?
-                    .parse()
-                    .map(|p| Instruction::Set(register, p))
-            }
-            "add" => {
-                let register = first.chars().next().expect(
-                    "There should be at least one character, since we used `split_whitespace`.",
-                );
-                second
-                    .ok_or_else(|| s.to_string())?
-                    .parse()
-                    .map(|p| Instruction::Add(register, p))
-            }
-            "sub" => {
-                let register = first.chars().next().expect(
-                    "There should be at least one character, since we used `split_whitespace`.",
-                );
-                second
-                    .ok_or_else(|| s.to_string())?
-                    .parse()
-                    .map(|p| Instruction::Sub(register, p))
-            }
-            "mul" => {
-                let register = first.chars().next().expect(
-                    "There should be at least one character, since we used `split_whitespace`.",
-                );
-                second
-                    .ok_or_else(|| s.to_string())?
-                    .parse()
-                    .map(|p| Instruction::Multiply(register, p))
-            }
-            "mod" => {
-                let register = first.chars().next().expect(
-                    "There should be at least one character, since we used `split_whitespace`.",
-                );
-                second
-                    .ok_or_else(|| s.to_string())?
-                    .parse()
-                    .map(|p| Instruction::Modulo(register, p))
-            }
-            "rcv" => {
-                let register = first.chars().next().expect(
-                    "There should be at least one character, since we used `split_whitespace`.",
-                );
-                Ok(Instruction::Receive(register))
-            }
-            "jgz" => {
-                let first = first.parse()?;
-                let second = second
-                    .ok_or_else(|| s.to_string())
-                    .and_then(|second| second.parse())?;
-                Ok(Instruction::JumpGreaterThanZero(first, second))
-            }
-            "jnz" => {
-                let first = first.parse()?;
-                let second = second
-                    .ok_or_else(|| s.to_string())
-                    .and_then(|second| second.parse())?;
-                Ok(Instruction::JumpIfNotZero(first, second))
-            }
-            _ => Err(format!("Unknown operation: {}", op)),
-        }
-    }
-}
-
-#[derive(Debug)]
-pub struct Computer {
-    registers: HashMap<Register, Value>,
+        let words: Vec<&str> = s.split_whitespace().collect();
+        Instruction::parse_words(&words).map_err(|(_, error)| error)
+    }
+}
+
+/// Why a line is not a valid instruction.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ParseError {
+    MissingOperation,
+    UnknownOperation(String),
+    /// A known operation, which the dialect does not accept.
+    NotAllowed(String),
+    /// Which one is missing: 1 or 2.
+    MissingParameter(usize),
+    InvalidRegister(String),
+    /// Neither a number nor a register.
+    InvalidParameter(String),
+    UnexpectedParameter(String),
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::MissingOperation => write!(f, "Missing operation."),
+            ParseError::UnknownOperation(op) => write!(f, "Unknown operation: {}", op),
+            ParseError::NotAllowed(op) => {
+                write!(f, "Operation not allowed in this dialect: {}", op)
+            }
+            ParseError::MissingParameter(1) => write!(f, "Missing first parameter."),
+            ParseError::MissingParameter(_) => write!(f, "Missing second parameter."),
+            ParseError::InvalidRegister(word) => write!(f, "Unknown register: {}", word),
+            ParseError::InvalidParameter(word) => write!(f, "Invalid parameter: {}", word),
+            ParseError::UnexpectedParameter(word) => write!(f, "Unexpected parameter: {}", word),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExecError {
+    /// A jump tried to go to a negative instruction.
+    InstructionPointerOutOfBounds(Value),
+    DivisionByZero,
+    Overflow,
+    Io(IoError),
+    StepLimitExhausted,
+    /// Through a `budget::CancelHandle`.
+    Cancelled,
+    /// An extra opcode that writes to something other than a register, which
+    /// only an `Instruction` built by hand can do.
+    InvalidOperand,
+}
+
+impl std::fmt::Display for ExecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExecError::InstructionPointerOutOfBounds(target) => {
+                write!(f, "instruction pointer out of bounds: {}", target)
+            }
+            ExecError::DivisionByZero => write!(f, "division by zero"),
+            ExecError::Overflow => write!(f, "arithmetic overflow"),
+            ExecError::Io(error) => write!(f, "{}", error),
+            ExecError::StepLimitExhausted => write!(f, "step limit exhausted"),
+            ExecError::Cancelled => write!(f, "cancelled"),
+            ExecError::InvalidOperand => write!(f, "invalid operand"),
+        }
+    }
+}
+
+impl std::error::Error for ExecError {}
+
+impl From<IoError> for ExecError {
+    fn from(error: IoError) -> Self {
+        ExecError::Io(error)
+    }
+}
+
+/// Why and in what state a `Computer` stopped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Halt {
+    /// `None` if the program simply ran past its last instruction.
+    pub error: Option<ExecError>,
+    pub steps: usize,
+    pub instruction_pointer: usize,
+    pub registers: Registers,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Status {
+    Running,
+    Blocked,
+    Halted,
+}
+
+/// What `snd` and `rcv` mean.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Semantics {
+    /// `snd X` plays a sound with a frequency of X, and `rcv X` recovers the
+    /// frequency of the last sound played, but only if X is not zero.
+    Sound,
+    /// `snd X` sends the value of X, and `rcv X` receives a value into X.
+    #[default]
+    Duet,
+}
+
+#[derive(Debug, Clone)]
+pub struct Computer<T = Channel> {
+    registers: Registers,
     instructions: Vec<Instruction>,
     instruction_pointer: usize,
-    sender: Sender<Value>,
-    receiver: Receiver<Value>,
+    io: T,
+    sent: usize,
+    semantics: Semantics,
+    last_played: Value,
+    recovered: Option<Value>,
+    arithmetic_mode: ArithmeticMode,
+    modulo_mode: ModuloMode,
+    /// Registers whose value does not fit in a `Value`, with
+    /// `ArithmeticMode::Big`.
+    #[cfg(feature = "bigint")]
+    big: std::collections::BTreeMap<Register, num_bigint::BigInt>,
+    history: Option<history::History>,
+    tracer: Option<Tracer>,
+    /// Shared with every clone, so that cancelling one cancels them all.
+    /// Created by `cancel_handle`.
+    cancel: Option<CancelHandle>,
 }
 
-impl Computer {
+impl Computer<Channel> {
     pub fn new(
         instructions: Vec<Instruction>,
         p: Value,
         sender: Sender<Value>,
         receiver: Receiver<Value>,
     ) -> Self {
-        let mut registers = HashMap::new();
-        registers.insert('p', p);
+        let mut computer = Computer::with_io(instructions, Channel::new(sender, receiver));
+        computer.set_register(Register::new('p').expect("`p` is a valid register."), p);
+        computer
+    }
+}
 
+impl<T: Io> Computer<T> {
+    pub fn with_io(instructions: Vec<Instruction>, io: T) -> Self {
         Computer {
-            registers,
+            registers: Registers::default(),
             instructions,
             instruction_pointer: 0,
-            sender,
-            receiver,
+            io,
+            sent: 0,
+            semantics: Semantics::default(),
+            last_played: 0,
+            recovered: None,
+            arithmetic_mode: ArithmeticMode::default(),
+            modulo_mode: ModuloMode::default(),
+            #[cfg(feature = "bigint")]
+            big: Default::default(),
+            history: None,
+            tracer: None,
+            cancel: None,
         }
     }
 
+    pub fn semantics(&self) -> Semantics {
+        self.semantics
+    }
+
+    pub fn set_semantics(&mut self, semantics: Semantics) {
+        self.semantics = semantics;
+    }
+
+    pub fn io(&self) -> &T {
+        &self.io
+    }
+
+    pub fn io_mut(&mut self) -> &mut T {
+        &mut self.io
+    }
+
+    pub fn into_io(self) -> T {
+        self.io
+    }
+
+    pub fn register(&self, register: Register) -> Value {
+        self.get_value(Parameter::Register(register))
+    }
+
+    pub fn set_register(&mut self, register: Register, value: Value) {
+        self.write(register, value);
+    }
+
+    pub fn sent(&self) -> usize {
+        self.sent
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.instruction_pointer >= self.instructions.len()
+    }
+
+    pub fn instruction_pointer(&self) -> usize {
+        self.instruction_pointer
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    pub fn registers(&self) -> &Registers {
+        &self.registers
+    }
+
+    /// The instruction about to be executed, if any.
+    pub fn current_instruction(&self) -> Option<Instruction> {
+        self.instructions.get(self.instruction_pointer).copied()
+    }
+
     fn get_value(&self, parameter: Parameter) -> Value {
         match parameter {
             Parameter::Value(v) => v,
-            Parameter::Register(r) => self.registers.get(&r).copied().unwrap_or_default(),
+            Parameter::Register(r) => self.registers[r],
         }
     }
 
-    fn step(&mut self) -> Result<(), RecvError> {
-        match self.instructions[self.instruction_pointer] {
-            Instruction::Send(p) => {
-                let _ = self.sender.send(self.get_value(p));
-                self.instruction_pointer += 1;
-            }
-            Instruction::Set(r, p) => {
-                self.registers.insert(r, self.get_value(p));
-                self.instruction_pointer += 1;
-            }
-            Instruction::Add(r, p) => {
-                *self.registers.entry(r).or_default() += self.get_value(p);
-                self.instruction_pointer += 1;
+    fn jump(&mut self, offset: Value) -> Result<(), ExecError> {
+        let target = Value::try_from(self.instruction_pointer)
+            .ok()
+            .and_then(|ip| ip.checked_add(offset))
+            .ok_or(ExecError::Overflow)?;
+        // Jumping past the end is how programs terminate, but there is nothing
+        // sensible to do with a negative target.
+        self.instruction_pointer = usize::try_from(target)
+            .map_err(|_| ExecError::InstructionPointerOutOfBounds(target))?;
+        Ok(())
+    }
+
+    fn send(&mut self, p: Parameter) -> Result<(), ExecError> {
+        match self.semantics {
+            Semantics::Sound => self.last_played = self.exact_value(p)?,
+            Semantics::Duet => self.io.send(self.exact_value(p)?)?,
+        }
+        self.sent += 1;
+        self.instruction_pointer += 1;
+        Ok(())
+    }
+
+    fn extended(&mut self, opcode: &Opcode, x: Parameter, y: Parameter) -> Result<(), ExecError> {
+        let register = || match x {
+            Parameter::Register(r) => Ok(r),
+            Parameter::Value(_) => Err(ExecError::InvalidOperand),
+        };
+
+        match opcode.behavior {
+            Behavior::Arithmetic(operation) => {
+                self.arithmetic(register()?, y, Operation::Custom(operation))
             }
-            Instruction::Sub(r, p) => {
-                *self.registers.entry(r).or_default() -= self.get_value(p);
-                self.instruction_pointer += 1;
+            Behavior::Division(operation) => {
+                let r = register()?;
+                if self.get_value(y) == 0 {
+                    return Err(ExecError::DivisionByZero);
+                }
+                self.arithmetic(r, y, Operation::Custom(operation))
             }
-            Instruction::Multiply(r, p) => {
-                *self.registers.entry(r).or_default() *= self.get_value(p);
-                self.instruction_pointer += 1;
+            Behavior::Jump(condition) => {
+                if condition(self.get_value(x)) {
+                    self.jump(self.exact_value(y)?)
+                } else {
+                    self.instruction_pointer += 1;
+                    Ok(())
+                }
             }
-            Instruction::Modulo(r, p) => {
-                *self.registers.entry(r).or_default() %= self.get_value(p);
+            Behavior::Send => self.send(x),
+        }
+    }
+
+    fn step(&mut self) -> Result<(), ExecError> {
+        if self.is_cancelled() {
+            return Err(ExecError::Cancelled);
+        }
+        let instruction = self.instructions[self.instruction_pointer];
+        self.recorded(|computer| computer.execute(instruction))
+    }
+
+    fn execute(&mut self, instruction: Instruction) -> Result<(), ExecError> {
+        match instruction {
+            Instruction::Send(p) => self.send(p)?,
+            Instruction::Set(r, p) => {
+                self.copy(r, p);
                 self.instruction_pointer += 1;
             }
-            Instruction::Receive(p) => {
-                self.registers.insert(p, self.receiver.recv()?);
+            Instruction::Add(r, p) => self.arithmetic(r, p, Operation::Add)?,
+            Instruction::Sub(r, p) => self.arithmetic(r, p, Operation::Sub)?,
+            Instruction::Multiply(r, p) => self.arithmetic(r, p, Operation::Multiply)?,
+            Instruction::Modulo(r, p) => self.arithmetic(r, p, Operation::Modulo)?,
+            Instruction::Receive(r) => {
+                match self.semantics {
+                    Semantics::Sound => {
+                        if self.get_value(Parameter::Register(r)) != 0 {
+                            self.recovered = Some(self.last_played);
+                        }
+                    }
+                    Semantics::Duet => {
+                        let value = self.io.receive()?;
+                        self.write(r, value);
+                    }
+                }
                 self.instruction_pointer += 1;
             }
             Instruction::JumpGreaterThanZero(r, p) => {
                 if self.get_value(r) > 0 {
-                    self.instruction_pointer =
-                        (self.instruction_pointer as Value + self.get_value(p)) as usize;
+                    self.jump(self.exact_value(p)?)?;
                 } else {
                     // This is necessary, otherwise we will never get out.
                     self.instruction_pointer += 1;
@@ -198,32 +550,91 @@ impl Computer {
             }
             Instruction::JumpIfNotZero(r, p) => {
                 if self.get_value(r) != 0 {
-                    self.instruction_pointer =
-                        (self.instruction_pointer as Value + self.get_value(p)) as usize;
+                    self.jump(self.exact_value(p)?)?;
                 } else {
                     // This is necessary, otherwise we will never get out.
                     self.instruction_pointer += 1;
                 }
             }
+            Instruction::Fused(fused) => self.fused(fused)?,
+            Instruction::Extended(opcode, x, y) => self.extended(opcode, x, y)?,
         }
 
         Ok(())
     }
 
-    pub fn debug_mode(&mut self) -> u32 {
-        let mut counter = 0;
-        while self.step().is_ok() && self.instruction_pointer < self.instructions.len() {
-            if matches!(
-                self.instructions[self.instruction_pointer],
-                Instruction::Multiply(_, _)
-            ) {
-                counter += 1;
+    /// Execute a single instruction without ever blocking on `Receive`. If the
+    /// input queue is empty, nothing is executed and `Status::Blocked` is
+    /// returned instead.
+    pub fn try_step(&mut self) -> Result<Status, ExecError> {
+        if self.is_halted() {
+            return Ok(Status::Halted);
+        }
+        if self.is_cancelled() {
+            return Err(ExecError::Cancelled);
+        }
+
+        if let (Semantics::Duet, Instruction::Receive(r)) =
+            (self.semantics, self.instructions[self.instruction_pointer])
+        {
+            match self.io.try_receive()? {
+                Some(value) => self.recorded(|computer| {
+                    computer.write(r, value);
+                    computer.instruction_pointer += 1;
+                    Ok(())
+                })?,
+                None => return Ok(Status::Blocked),
+            }
+        } else {
+            self.step()?;
+        }
+
+        Ok(Status::Running)
+    }
+
+    /// Run until the program exits, an error occurs or, if given, the step
+    /// limit is reached.
+    pub fn run_until_halt(&mut self, step_limit: Option<usize>) -> Halt {
+        let mut steps = 0;
+        let error = loop {
+            if self.is_halted() {
+                break None;
+            }
+            if step_limit.is_some_and(|limit| steps >= limit) {
+                break Some(ExecError::StepLimitExhausted);
             }
+            if let Err(error) = self.step() {
+                break Some(error);
+            }
+            steps += 1;
+        };
+
+        Halt {
+            error,
+            steps,
+            instruction_pointer: self.instruction_pointer,
+            registers: self.registers,
         }
-        counter
+    }
+
+    /// Run the program, and count how many times `mul` was executed.
+    pub fn debug_mode(&mut self) -> usize {
+        self.profile().opcode("mul")
+    }
+
+    /// Run until a `rcv` recovers a frequency, with `Semantics::Sound`.
+    /// Returns `None` if the program stops first.
+    pub fn recover(&mut self) -> Option<Value> {
+        while !self.is_halted() && self.step().is_ok() {
+            if let Some(frequency) = self.recovered.take() {
+                return Some(frequency);
+            }
+        }
+
+        None
     }
 
     pub fn run(&mut self) {
-        while self.step().is_ok() && self.instruction_pointer < self.instructions.len() {}
+        while !self.is_halted() && self.step().is_ok() {}
     }
 }
@@ -0,0 +1,335 @@
+use super::{Instruction, Parameter, Value};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Target {
+    Block(usize),
+    /// Anywhere outside of the program, which terminates it.
+    Exit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EdgeKind {
+    /// The next instruction, either because the block simply ends there or
+    /// because the jump was not taken.
+    Fallthrough,
+    Jump,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Edge {
+    pub kind: EdgeKind,
+    pub target: Target,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BasicBlock {
+    /// First instruction of the block.
+    pub start: usize,
+    /// One past the last instruction of the block.
+    pub end: usize,
+    pub successors: Vec<Edge>,
+    /// Ends with a jump whose offset is held in a register, so where it goes
+    /// can only be known at runtime.
+    pub dynamic: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Loop {
+    pub header: usize,
+    /// Blocks that jump back to the header.
+    pub latches: BTreeSet<usize>,
+    /// Every block of the loop, including the header and the nested loops.
+    pub blocks: BTreeSet<usize>,
+    /// Index of the innermost loop containing this one.
+    pub parent: Option<usize>,
+    /// 1 for outermost loops.
+    pub depth: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ControlFlowGraph {
+    instructions: Vec<Instruction>,
+    pub blocks: Vec<BasicBlock>,
+}
+
+/// Whether a jump on this condition is always (`Some(true)`) or never
+/// (`Some(false)`) taken, if it can be known without running the program.
+fn constant_condition(instruction: Instruction) -> Option<bool> {
+    match instruction.jump()? {
+        (Parameter::Value(v), _) => instruction.is_taken(v),
+        _ => None,
+    }
+}
+
+fn jump_offset(instruction: Instruction) -> Option<Parameter> {
+    match instruction {
+        // Either skips the whole idiom, or falls back to the next instruction.
+        Instruction::Fused(fused) => Some(Parameter::Value(fused.span() as Value)),
+        _ => instruction.jump().map(|(_, offset)| offset),
+    }
+}
+
+impl ControlFlowGraph {
+    pub fn new(instructions: &[Instruction]) -> Self {
+        let target = |pc: usize, offset: Value| {
+            (pc as Value)
+                .checked_add(offset)
+                .and_then(|target| usize::try_from(target).ok())
+                .filter(|&target| target < instructions.len())
+        };
+
+        // Blocks start at the beginning, at every jump target and right after
+        // every jump.
+        let mut leaders = BTreeSet::new();
+        leaders.insert(0);
+        for (pc, &instruction) in instructions.iter().enumerate() {
+            if let Some(offset) = jump_offset(instruction) {
+                leaders.insert(pc + 1);
+                if let Parameter::Value(offset) = offset {
+                    leaders.extend(target(pc, offset));
+                }
+            }
+        }
+        let leaders: Vec<usize> = leaders
+            .into_iter()
+            .filter(|&pc| pc < instructions.len())
+            .collect();
+
+        let block_at = |pc: usize| {
+            leaders
+                .binary_search(&pc)
+                .map_or(Target::Exit, Target::Block)
+        };
+
+        let blocks = leaders
+            .iter()
+            .enumerate()
+            .map(|(idx, &start)| {
+                let end = leaders.get(idx + 1).copied().unwrap_or(instructions.len());
+                let last = instructions[end - 1];
+                let mut successors = Vec::new();
+                let mut dynamic = false;
+
+                match jump_offset(last) {
+                    Some(offset) => {
+                        let condition = constant_condition(last);
+                        if condition != Some(false) {
+                            match offset {
+                                Parameter::Value(offset) => successors.push(Edge {
+                                    kind: EdgeKind::Jump,
+                                    target: target(end - 1, offset).map_or(Target::Exit, block_at),
+                                }),
+                                Parameter::Register(_) => dynamic = true,
+                            }
+                        }
+                        if condition != Some(true) {
+                            successors.push(Edge {
+                                kind: EdgeKind::Fallthrough,
+                                target: block_at(end),
+                            });
+                        }
+                    }
+                    None => successors.push(Edge {
+                        kind: EdgeKind::Fallthrough,
+                        target: block_at(end),
+                    }),
+                }
+
+                BasicBlock {
+                    start,
+                    end,
+                    successors,
+                    dynamic,
+                }
+            })
+            .collect();
+
+        ControlFlowGraph {
+            instructions: instructions.to_vec(),
+            blocks,
+        }
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// The block containing the instruction at `pc`.
+    pub fn block_of(&self, pc: usize) -> Option<usize> {
+        match self.blocks.binary_search_by_key(&pc, |block| block.start) {
+            Ok(idx) => Some(idx),
+            Err(0) => None,
+            Err(idx) => Some(idx - 1).filter(|_| pc < self.instructions.len()),
+        }
+    }
+
+    pub fn successors(&self, block: usize) -> impl Iterator<Item = usize> + '_ {
+        self.blocks[block]
+            .successors
+            .iter()
+            .filter_map(|edge| match edge.target {
+                Target::Block(target) => Some(target),
+                Target::Exit => None,
+            })
+    }
+
+    pub fn predecessors(&self, block: usize) -> Vec<usize> {
+        (0..self.blocks.len())
+            .filter(|&idx| self.successors(idx).any(|target| target == block))
+            .collect()
+    }
+
+    /// Blocks that can be reached from the start of the program.
+    pub fn reachable(&self) -> BTreeSet<usize> {
+        let mut reachable = BTreeSet::new();
+        let mut to_visit: Vec<usize> = (!self.blocks.is_empty()).then_some(0).into_iter().collect();
+
+        while let Some(block) = to_visit.pop() {
+            if reachable.insert(block) {
+                to_visit.extend(self.successors(block));
+            }
+        }
+
+        reachable
+    }
+
+    /// For every block, the set of blocks that every path from the start must
+    /// go through to reach it. Empty for unreachable blocks.
+    pub fn dominators(&self) -> Vec<BTreeSet<usize>> {
+        let reachable = self.reachable();
+        let mut dominators: Vec<BTreeSet<usize>> = (0..self.blocks.len())
+            .map(|idx| {
+                if idx == 0 {
+                    BTreeSet::from([0])
+                } else if reachable.contains(&idx) {
+                    reachable.clone()
+                } else {
+                    BTreeSet::new()
+                }
+            })
+            .collect();
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in reachable.iter().skip(1) {
+                let mut new = self
+                    .predecessors(block)
+                    .into_iter()
+                    .filter(|p| reachable.contains(p))
+                    .map(|p| dominators[p].clone())
+                    .reduce(|acc, d| acc.intersection(&d).copied().collect())
+                    .unwrap_or_default();
+                new.insert(block);
+
+                if new != dominators[block] {
+                    dominators[block] = new;
+                    changed = true;
+                }
+            }
+        }
+
+        dominators
+    }
+
+    /// Natural loops, outermost first. Loops sharing a header are merged.
+    pub fn loops(&self) -> Vec<Loop> {
+        let dominators = self.dominators();
+        let mut loops: Vec<Loop> = Vec::new();
+
+        for (block, dominated_by) in dominators.iter().enumerate() {
+            for header in self.successors(block) {
+                if !dominated_by.contains(&header) {
+                    continue;
+                }
+
+                // Everything that can reach the latch without going through
+                // the header is part of the loop.
+                let mut body = BTreeSet::from([header]);
+                let mut to_visit = vec![block];
+                while let Some(current) = to_visit.pop() {
+                    if body.insert(current) {
+                        to_visit.extend(
+                            self.predecessors(current)
+                                .into_iter()
+                                .filter(|&p| !dominators[p].is_empty()),
+                        );
+                    }
+                }
+
+                match loops.iter_mut().find(|l| l.header == header) {
+                    Some(existing) => {
+                        existing.latches.insert(block);
+                        existing.blocks.extend(body);
+                    }
+                    None => loops.push(Loop {
+                        header,
+                        latches: BTreeSet::from([block]),
+                        blocks: body,
+                        parent: None,
+                        depth: 1,
+                    }),
+                }
+            }
+        }
+
+        // Bigger loops first, so parents always come before their children.
+        loops.sort_by(|a, b| {
+            b.blocks
+                .len()
+                .cmp(&a.blocks.len())
+                .then(a.header.cmp(&b.header))
+        });
+        for idx in 0..loops.len() {
+            let parent = (0..idx)
+                .rev()
+                .find(|&p| loops[p].blocks.is_superset(&loops[idx].blocks));
+            loops[idx].parent = parent;
+            loops[idx].depth = parent.map_or(1, |p| loops[p].depth + 1);
+        }
+
+        loops
+    }
+
+    /// Graphviz representation of the graph, with loop headers highlighted
+    /// and dynamic jumps dashed.
+    pub fn to_dot(&self) -> String {
+        let headers: BTreeSet<usize> = self.loops().iter().map(|l| l.header).collect();
+        let mut output = String::from("digraph cfg {\n    node [shape=box, fontname=monospace];\n");
+
+        for (idx, block) in self.blocks.iter().enumerate() {
+            let label: String = (block.start..block.end)
+                .map(|pc| format!("{}: {}\\l", pc, self.instructions[pc]))
+                .collect::<String>()
+                .replace('"', "\\\"");
+            let mut style = String::new();
+            if headers.contains(&idx) {
+                style.push_str(", penwidth=2");
+            }
+            if block.dynamic {
+                style.push_str(", style=dashed");
+            }
+            let _ = writeln!(output, "    b{} [label=\"{}\"{}];", idx, label, style);
+        }
+        output.push_str("    exit [shape=doublecircle];\n");
+
+        for (idx, block) in self.blocks.iter().enumerate() {
+            for edge in &block.successors {
+                let target = match edge.target {
+                    Target::Block(target) => format!("b{}", target),
+                    Target::Exit => "exit".to_string(),
+                };
+                let label = match edge.kind {
+                    EdgeKind::Fallthrough => "",
+                    EdgeKind::Jump => " [label=\"jump\"]",
+                };
+                let _ = writeln!(output, "    b{} -> {}{};", idx, target, label);
+            }
+        }
+        output.push_str("}\n");
+
+        output
+    }
+}
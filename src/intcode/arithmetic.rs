@@ -0,0 +1,224 @@
+use super::io::Io;
+use super::{Computer, ExecError, Parameter, Register, Value};
+#[cfg(feature = "bigint")]
+use num_bigint::{BigInt, Sign};
+
+/// What happens when a result does not fit in a `Value`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArithmeticMode {
+    Wrapping,
+    /// Stop with `ExecError::Overflow`.
+    #[default]
+    Checked,
+    /// Clamp to `Value::MIN` or `Value::MAX`.
+    Saturating,
+    /// Keep every digit. As a jump condition, such a register reads as
+    /// `Value::MIN` or `Value::MAX`, which has the right sign; using it as a
+    /// jump offset or sending it is an `ExecError::Overflow`.
+    #[cfg(feature = "bigint")]
+    Big,
+}
+
+/// What `mod` does with negative operands.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModuloMode {
+    /// Like Rust's `%`: the result has the sign of the dividend, so
+    /// `-7 mod 3` is -1.
+    #[default]
+    Truncated,
+    /// The result is never negative, so `-7 mod 3` is 2.
+    Euclidean,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Operation {
+    Add,
+    Sub,
+    Multiply,
+    Modulo,
+    /// From an extra opcode, which only knows how to check for overflow.
+    Custom(fn(Value, Value) -> Option<Value>),
+}
+
+impl Operation {
+    fn checked(self, a: Value, b: Value, modulo: ModuloMode) -> Option<Value> {
+        match (self, modulo) {
+            (Operation::Add, _) => a.checked_add(b),
+            (Operation::Sub, _) => a.checked_sub(b),
+            (Operation::Multiply, _) => a.checked_mul(b),
+            (Operation::Modulo, ModuloMode::Truncated) => a.checked_rem(b),
+            (Operation::Modulo, ModuloMode::Euclidean) => a.checked_rem_euclid(b),
+            (Operation::Custom(operation), _) => operation(a, b),
+        }
+    }
+
+    fn wrapping(self, a: Value, b: Value, modulo: ModuloMode) -> Option<Value> {
+        match (self, modulo) {
+            (Operation::Add, _) => Some(a.wrapping_add(b)),
+            (Operation::Sub, _) => Some(a.wrapping_sub(b)),
+            (Operation::Multiply, _) => Some(a.wrapping_mul(b)),
+            (Operation::Modulo, ModuloMode::Truncated) => Some(a.wrapping_rem(b)),
+            (Operation::Modulo, ModuloMode::Euclidean) => Some(a.wrapping_rem_euclid(b)),
+            (Operation::Custom(operation), _) => operation(a, b),
+        }
+    }
+
+    fn saturating(self, a: Value, b: Value, modulo: ModuloMode) -> Option<Value> {
+        match self {
+            Operation::Add => Some(a.saturating_add(b)),
+            Operation::Sub => Some(a.saturating_sub(b)),
+            Operation::Multiply => Some(a.saturating_mul(b)),
+            // Only `Value::MIN mod -1` overflows, and 0 is the right answer.
+            Operation::Modulo => self.wrapping(a, b, modulo),
+            Operation::Custom(operation) => operation(a, b),
+        }
+    }
+
+    #[cfg(feature = "bigint")]
+    fn big(self, a: BigInt, b: BigInt, modulo: ModuloMode) -> Option<BigInt> {
+        match (self, modulo) {
+            (Operation::Add, _) => Some(a + b),
+            (Operation::Sub, _) => Some(a - b),
+            (Operation::Multiply, _) => Some(a * b),
+            (Operation::Modulo, ModuloMode::Truncated) => Some(a % b),
+            (Operation::Modulo, ModuloMode::Euclidean) => {
+                let remainder = a % &b;
+                if remainder.sign() == Sign::Minus {
+                    Some(remainder + BigInt::from(b.magnitude().clone()))
+                } else {
+                    Some(remainder)
+                }
+            }
+            (Operation::Custom(operation), _) => {
+                operation(Value::try_from(&a).ok()?, Value::try_from(&b).ok()?).map(BigInt::from)
+            }
+        }
+    }
+}
+
+impl<T: Io> Computer<T> {
+    pub fn arithmetic_mode(&self) -> ArithmeticMode {
+        self.arithmetic_mode
+    }
+
+    pub fn set_arithmetic_mode(&mut self, mode: ArithmeticMode) {
+        self.arithmetic_mode = mode;
+    }
+
+    pub fn modulo_mode(&self) -> ModuloMode {
+        self.modulo_mode
+    }
+
+    pub fn set_modulo_mode(&mut self, mode: ModuloMode) {
+        self.modulo_mode = mode;
+    }
+
+    /// `r = r <operation> p`.
+    pub(super) fn arithmetic(
+        &mut self,
+        r: Register,
+        p: Parameter,
+        operation: Operation,
+    ) -> Result<(), ExecError> {
+        let (a, b) = (self.registers[r], self.get_value(p));
+        if matches!(operation, Operation::Modulo) && b == 0 {
+            return Err(ExecError::DivisionByZero);
+        }
+
+        let result = match self.arithmetic_mode {
+            ArithmeticMode::Wrapping => operation.wrapping(a, b, self.modulo_mode),
+            ArithmeticMode::Checked => operation.checked(a, b, self.modulo_mode),
+            ArithmeticMode::Saturating => operation.saturating(a, b, self.modulo_mode),
+            #[cfg(feature = "bigint")]
+            ArithmeticMode::Big => {
+                let result = operation
+                    .big(
+                        self.big_value(Parameter::Register(r)),
+                        self.big_value(p),
+                        self.modulo_mode,
+                    )
+                    .ok_or(ExecError::Overflow)?;
+                self.set_big_register(r, result);
+                self.instruction_pointer += 1;
+                return Ok(());
+            }
+        };
+
+        self.write(r, result.ok_or(ExecError::Overflow)?);
+        self.instruction_pointer += 1;
+        Ok(())
+    }
+
+    /// Every write to a register goes through here, so that a big value does
+    /// not outlive it.
+    pub(super) fn write(&mut self, r: Register, value: Value) {
+        self.registers[r] = value;
+        #[cfg(feature = "bigint")]
+        self.big.remove(&r);
+    }
+
+    /// `r = p`, keeping every digit of `p`.
+    pub(super) fn copy(&mut self, r: Register, p: Parameter) {
+        self.write(r, self.get_value(p));
+        #[cfg(feature = "bigint")]
+        if let Parameter::Register(source) = p {
+            if let Some(value) = self.big.get(&source).cloned() {
+                self.big.insert(r, value);
+            }
+        }
+    }
+
+    /// `Ok(value)`, unless it is only part of a big value.
+    pub(super) fn exact_value(&self, p: Parameter) -> Result<Value, ExecError> {
+        #[cfg(feature = "bigint")]
+        if let Parameter::Register(r) = p {
+            if self.big.contains_key(&r) {
+                return Err(ExecError::Overflow);
+            }
+        }
+        Ok(self.get_value(p))
+    }
+
+    /// Whether any register holds a value too large for a `Value`.
+    #[cfg(feature = "bigint")]
+    pub(super) fn has_big_values(&self) -> bool {
+        !self.big.is_empty()
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    pub(super) fn has_big_values(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl<T: Io> Computer<T> {
+    /// The exact value of a register, even if it does not fit in a `Value`.
+    pub fn big_register(&self, register: Register) -> BigInt {
+        self.big_value(Parameter::Register(register))
+    }
+
+    pub fn set_big_register(&mut self, register: Register, value: BigInt) {
+        match Value::try_from(&value) {
+            Ok(value) => self.write(register, value),
+            Err(_) => {
+                self.registers[register] = match value.sign() {
+                    Sign::Minus => Value::MIN,
+                    _ => Value::MAX,
+                };
+                self.big.insert(register, value);
+            }
+        }
+    }
+
+    fn big_value(&self, p: Parameter) -> BigInt {
+        match p {
+            Parameter::Register(r) => self
+                .big
+                .get(&r)
+                .cloned()
+                .unwrap_or_else(|| BigInt::from(self.registers[r])),
+            Parameter::Value(v) => BigInt::from(v),
+        }
+    }
+}
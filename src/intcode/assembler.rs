@@ -0,0 +1,196 @@
+use super::dialect::Dialect;
+use super::{Instruction, Register, Value};
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// A problem with the source, at a given position (both starting from 1).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// A word of the source, and the column it starts at.
+#[derive(Debug, Clone, Copy)]
+struct Word<'a> {
+    text: &'a str,
+    column: usize,
+}
+
+fn words(line: &str) -> Vec<Word<'_>> {
+    let mut words = Vec::new();
+    let mut start = None;
+
+    for (idx, c) in line
+        .char_indices()
+        .chain(std::iter::once((line.len(), ' ')))
+    {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some(idx),
+            (Some(s), true) => {
+                words.push(Word {
+                    text: &line[s..idx],
+                    column: line[..s].chars().count() + 1,
+                });
+                start = None;
+            }
+            _ => (),
+        }
+    }
+
+    words
+}
+
+fn is_label(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Assemble a program, where:
+/// * everything after a `#` is a comment;
+/// * blank lines are ignored;
+/// * `name:` at the start of a line defines a label for the next instruction,
+///   which jumps can then use instead of a relative offset;
+/// * `12:` at the start of a line is the address of the next instruction, as
+///   in the output of `disassembler::disassemble`, and must be right.
+///
+/// Labels can not be named like registers, since `jnz a b` would otherwise be
+/// ambiguous. Every problem found is reported, not just the first one.
+///
+/// ```text
+/// # Count down from 10.
+///         set a 10
+/// again:  sub a 1
+///         jnz a again
+/// ```
+pub fn assemble(source: &str) -> Result<Vec<Instruction>, Vec<Diagnostic>> {
+    assemble_with(source, &Dialect::default())
+}
+
+/// Same as `assemble`, but only with the operations of `dialect`.
+pub fn assemble_with(source: &str, dialect: &Dialect) -> Result<Vec<Instruction>, Vec<Diagnostic>> {
+    let mut diagnostics = Vec::new();
+    let mut labels: HashMap<&str, usize> = HashMap::new();
+    let mut lines = Vec::new();
+
+    // Find the labels first, since they can be used before being defined.
+    for (idx, line) in source.lines().enumerate() {
+        let code = line.split('#').next().unwrap_or_default();
+        let mut words = words(code);
+
+        while let Some(label) = words.first().and_then(|word| word.text.strip_suffix(':')) {
+            let column = words[0].column;
+            if let Ok(address) = label.parse::<usize>() {
+                if address != lines.len() {
+                    diagnostics.push(Diagnostic {
+                        line: idx + 1,
+                        column,
+                        message: format!("Wrong address {}, expected {}", address, lines.len()),
+                    });
+                }
+            } else if !is_label(label) {
+                diagnostics.push(Diagnostic {
+                    line: idx + 1,
+                    column,
+                    message: format!("Invalid label name: {}", label),
+                });
+            } else if label.parse::<Register>().is_ok() {
+                diagnostics.push(Diagnostic {
+                    line: idx + 1,
+                    column,
+                    message: format!("Label is named like a register: {}", label),
+                });
+            } else if labels.insert(label, lines.len()).is_some() {
+                diagnostics.push(Diagnostic {
+                    line: idx + 1,
+                    column,
+                    message: format!("Duplicate label: {}", label),
+                });
+            }
+            words.remove(0);
+        }
+
+        if !words.is_empty() {
+            lines.push((idx + 1, words));
+        }
+    }
+
+    let mut instructions = Vec::new();
+    for (pc, (line, words)) in lines.into_iter().enumerate() {
+        let mut texts: Vec<String> = words.iter().map(|word| word.text.to_string()).collect();
+
+        // Replace a label in the offset of a jump by the actual offset.
+        if words.first().is_some_and(|word| dialect.is_jump(word.text)) {
+            if let Some(offset) = words.get(2) {
+                let is_number = offset.text.parse::<Value>().is_ok();
+                let is_register = offset.text.parse::<Register>().is_ok();
+                if !is_number && !is_register {
+                    match labels.get(offset.text) {
+                        Some(&target) => texts[2] = (target as Value - pc as Value).to_string(),
+                        None => {
+                            diagnostics.push(Diagnostic {
+                                line,
+                                column: offset.column,
+                                message: format!("Unknown label: {}", offset.text),
+                            });
+                            continue;
+                        }
+                    }
+                }
+            }
+        }
+
+        let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+        match dialect.parse_words(&texts) {
+            Ok(instruction) => instructions.push(instruction),
+            Err((idx, message)) => {
+                // A missing word is reported right after the last one.
+                let column = words.get(idx).map_or_else(
+                    || {
+                        let last = words[words.len() - 1];
+                        last.column + last.text.chars().count()
+                    },
+                    |word| word.column,
+                );
+                diagnostics.push(Diagnostic {
+                    line,
+                    column,
+                    message: message.to_string(),
+                });
+            }
+        }
+    }
+
+    if diagnostics.is_empty() {
+        Ok(instructions)
+    } else {
+        diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+        Err(diagnostics)
+    }
+}
+
+/// The line (starting from 1) of every instruction in `source`, in the order
+/// `assemble` would return them.
+pub fn line_numbers(source: &str) -> Vec<usize> {
+    source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let code = line.split('#').next().unwrap_or_default();
+            words(code).iter().any(|word| !word.text.ends_with(':'))
+        })
+        .map(|(idx, _)| idx + 1)
+        .collect()
+}
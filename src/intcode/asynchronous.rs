@@ -0,0 +1,262 @@
+use super::io::{IoError, Queue};
+use super::{Computer, ExecError, Halt, Instruction, Status, Value};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+/// How many steps an `AsyncComputer` runs before letting other tasks run.
+const STEPS_PER_YIELD: usize = 1024;
+
+/// Where an `AsyncComputer` sends its values to, and receives them from.
+/// Nothing here depends on a particular executor.
+pub trait AsyncIo {
+    fn send(&mut self, value: Value) -> impl Future<Output = Result<(), IoError>>;
+
+    /// Wait for a value.
+    fn receive(&mut self) -> impl Future<Output = Result<Value, IoError>>;
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    values: VecDeque<Value>,
+    senders: usize,
+    /// The receiver, if it is waiting for a value.
+    waker: Option<Waker>,
+}
+
+fn lock(shared: &Mutex<Shared>) -> MutexGuard<'_, Shared> {
+    // Only the queue and the counter are touched while holding the lock
+    // (wakers are called after releasing it), so nothing can panic and
+    // poison it.
+    shared
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// The sending half of a `pipe`. It can be cloned, to have several senders.
+#[derive(Debug)]
+pub struct PipeSender(Arc<Mutex<Shared>>);
+
+/// The receiving half of a `pipe`.
+#[derive(Debug)]
+pub struct PipeReceiver(Arc<Mutex<Shared>>);
+
+/// An unbounded queue of values, whose receiver can wait without blocking
+/// its thread. Receiving fails once the queue is empty and every sender is
+/// gone.
+pub fn pipe() -> (PipeSender, PipeReceiver) {
+    let shared = Arc::new(Mutex::new(Shared {
+        senders: 1,
+        ..Shared::default()
+    }));
+    (PipeSender(shared.clone()), PipeReceiver(shared))
+}
+
+impl PipeSender {
+    /// Never waits, since the queue is unbounded.
+    pub fn send(&self, value: Value) {
+        let waker = {
+            let mut shared = lock(&self.0);
+            shared.values.push_back(value);
+            shared.waker.take()
+        };
+        // Not under the lock, since waking may poll the receiver right away.
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl Clone for PipeSender {
+    fn clone(&self) -> Self {
+        lock(&self.0).senders += 1;
+        PipeSender(self.0.clone())
+    }
+}
+
+impl Drop for PipeSender {
+    fn drop(&mut self) {
+        let waker = {
+            let mut shared = lock(&self.0);
+            shared.senders -= 1;
+            match shared.senders {
+                0 => shared.waker.take(),
+                _ => None,
+            }
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl PipeReceiver {
+    pub fn receive(&mut self) -> Receive<'_> {
+        Receive(self)
+    }
+
+    /// Get a value if there is one available right now, without waiting.
+    pub fn try_receive(&mut self) -> Result<Option<Value>, IoError> {
+        let mut shared = lock(&self.0);
+        match shared.values.pop_front() {
+            Some(value) => Ok(Some(value)),
+            None if shared.senders == 0 => Err(IoError::Disconnected),
+            None => Ok(None),
+        }
+    }
+}
+
+/// The future returned by `PipeReceiver::receive`.
+#[derive(Debug)]
+pub struct Receive<'a>(&'a mut PipeReceiver);
+
+impl Future for Receive<'_> {
+    type Output = Result<Value, IoError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Checking for a value, checking for senders and registering the
+        // waker all under the same lock, so that no wakeup is lost between them.
+        let mut shared = lock(&self.0 .0);
+        match shared.values.pop_front() {
+            Some(value) => Poll::Ready(Ok(value)),
+            None if shared.senders == 0 => Poll::Ready(Err(IoError::Disconnected)),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Sends through one pipe, and receives from another.
+#[derive(Debug)]
+pub struct Pipe {
+    sender: PipeSender,
+    receiver: PipeReceiver,
+}
+
+impl Pipe {
+    pub fn new(sender: PipeSender, receiver: PipeReceiver) -> Self {
+        Pipe { sender, receiver }
+    }
+}
+
+impl AsyncIo for Pipe {
+    async fn send(&mut self, value: Value) -> Result<(), IoError> {
+        self.sender.send(value);
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> Result<Value, IoError> {
+        self.receiver.receive().await
+    }
+}
+
+/// Returns `Pending` once, so that executors which poll tasks in turns get
+/// to run the others.
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+/// A `Computer` whose `rcv` waits on a future instead of blocking its
+/// thread, so that a single thread can run as many of them as needed.
+///
+/// Every instruction is still executed by the synchronous `Computer`, on a
+/// `Queue`: what it sends is forwarded to the `AsyncIo`, and when it would
+/// block on `rcv`, a value is awaited from the `AsyncIo` and queued for it.
+#[derive(Debug)]
+pub struct AsyncComputer<T> {
+    computer: Computer<Queue>,
+    io: T,
+}
+
+impl<T: AsyncIo> AsyncComputer<T> {
+    pub fn new(instructions: Vec<Instruction>, io: T) -> Self {
+        AsyncComputer {
+            computer: Computer::with_io(instructions, Queue::default()),
+            io,
+        }
+    }
+
+    /// The synchronous computer underneath, to set registers or modes, or
+    /// to enable the history or the tracer.
+    pub fn computer(&self) -> &Computer<Queue> {
+        &self.computer
+    }
+
+    pub fn computer_mut(&mut self) -> &mut Computer<Queue> {
+        &mut self.computer
+    }
+
+    pub fn io(&self) -> &T {
+        &self.io
+    }
+
+    pub fn io_mut(&mut self) -> &mut T {
+        &mut self.io
+    }
+
+    pub fn into_parts(self) -> (Computer<Queue>, T) {
+        (self.computer, self.io)
+    }
+
+    /// Forward everything sent so far.
+    async fn flush(&mut self) -> Result<(), IoError> {
+        while let Some(value) = self.computer.io_mut().output.pop_front() {
+            self.io.send(value).await?;
+        }
+        Ok(())
+    }
+
+    /// Like `Computer::run_until_halt`.
+    pub async fn run_until_halt(&mut self, step_limit: Option<usize>) -> Halt {
+        let mut steps = 0;
+        let error = loop {
+            if let Err(error) = self.flush().await {
+                break Some(error.into());
+            }
+            if self.computer.is_halted() {
+                break None;
+            }
+            if step_limit.is_some_and(|limit| steps >= limit) {
+                break Some(ExecError::StepLimitExhausted);
+            }
+
+            match self.computer.try_step() {
+                Ok(Status::Running) => {
+                    steps += 1;
+                    if steps % STEPS_PER_YIELD == 0 {
+                        YieldNow(false).await;
+                    }
+                }
+                Ok(Status::Blocked) => match self.io.receive().await {
+                    Ok(value) => self.computer.io_mut().input.push_back(value),
+                    Err(error) => break Some(error.into()),
+                },
+                Ok(Status::Halted) => break None,
+                Err(error) => break Some(error),
+            }
+        };
+
+        Halt {
+            error,
+            steps,
+            instruction_pointer: self.computer.instruction_pointer(),
+            registers: *self.computer.registers(),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use super::io::Io;
+use super::{Computer, ExecError};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Stops a `Computer` from another thread. Every clone controls the same
+/// computer, which stops with `ExecError::Cancelled` before its next step.
+#[derive(Debug, Default, Clone)]
+pub struct CancelHandle(Arc<AtomicBool>);
+
+impl CancelHandle {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Let the computer run again.
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+/// How a run with a budget ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    /// The program ran past its last instruction.
+    Halted {
+        steps: usize,
+    },
+    /// Every step of the budget was used, and the program is still running.
+    OutOfFuel,
+    Cancelled {
+        steps: usize,
+    },
+    Error(ExecError),
+}
+
+impl Outcome {
+    /// How a run that took `steps` steps and stopped on `error` ended.
+    pub(super) fn new(error: Option<ExecError>, steps: usize) -> Self {
+        match error {
+            None => Outcome::Halted { steps },
+            Some(ExecError::StepLimitExhausted) => Outcome::OutOfFuel,
+            Some(ExecError::Cancelled) => Outcome::Cancelled { steps },
+            Some(error) => Outcome::Error(error),
+        }
+    }
+}
+
+impl<T: Io> Computer<T> {
+    /// A handle to cancel this computer with, from anywhere.
+    ///
+    /// The handle is created by the first call, and every clone of the
+    /// computer made after that shares it: cancelling one of them stops
+    /// them all, and resetting it lets them all run again.
+    pub fn cancel_handle(&mut self) -> CancelHandle {
+        self.cancel
+            .get_or_insert_with(CancelHandle::default)
+            .clone()
+    }
+
+    pub(super) fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancelHandle::is_cancelled)
+    }
+
+    /// Run for at most `max_steps` steps.
+    pub fn run_with_budget(&mut self, max_steps: usize) -> Outcome {
+        let halt = self.run_until_halt(Some(max_steps));
+        Outcome::new(halt.error, halt.steps)
+    }
+}
@@ -0,0 +1,197 @@
+use super::arithmetic::{ArithmeticMode, ModuloMode};
+use super::io::Io;
+use super::{Computer, ExecError, Halt, Instruction, Parameter, Register, Value};
+
+type Op<T> = Box<dyn Fn(&mut Computer<T>) -> Result<(), ExecError>>;
+
+/// A program lowered to one closure per instruction, with its operands,
+/// jump targets and arithmetic mode resolved once, instead of at every step.
+///
+/// Only the plain instructions are specialized; everything else (`snd`,
+/// `rcv`, superinstructions, extra opcodes, and arithmetic in a mode other
+/// than the default one) calls back into the interpreter, which remains
+/// the reference.
+pub struct Compiled<T> {
+    instructions: Vec<Instruction>,
+    ops: Vec<Op<T>>,
+    arithmetic_mode: ArithmeticMode,
+    modulo_mode: ModuloMode,
+}
+
+impl<T> std::fmt::Debug for Compiled<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Compiled")
+            .field("instructions", &self.instructions)
+            .field("arithmetic_mode", &self.arithmetic_mode)
+            .field("modulo_mode", &self.modulo_mode)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Where a jump at `pc` lands, or the error the interpreter would give.
+fn target(pc: usize, offset: Value) -> Result<usize, ExecError> {
+    let target = Value::try_from(pc)
+        .ok()
+        .and_then(|pc| pc.checked_add(offset))
+        .ok_or(ExecError::Overflow)?;
+    usize::try_from(target).map_err(|_| ExecError::InstructionPointerOutOfBounds(target))
+}
+
+/// `r = f(r, p)`.
+fn binary<T: Io>(
+    r: Register,
+    p: Parameter,
+    next: usize,
+    f: fn(Value, Value) -> Result<Value, ExecError>,
+) -> Op<T> {
+    match p {
+        Parameter::Value(v) => Box::new(move |computer| {
+            let value = f(computer.registers[r], v)?;
+            computer.write(r, value);
+            computer.instruction_pointer = next;
+            Ok(())
+        }),
+        Parameter::Register(source) => Box::new(move |computer| {
+            let value = f(computer.registers[r], computer.registers[source])?;
+            computer.write(r, value);
+            computer.instruction_pointer = next;
+            Ok(())
+        }),
+    }
+}
+
+fn branch<T: Io>(
+    x: Parameter,
+    target: Result<usize, ExecError>,
+    next: usize,
+    taken: fn(Value) -> bool,
+) -> Op<T> {
+    match x {
+        Parameter::Value(v) if taken(v) => Box::new(move |computer| {
+            computer.instruction_pointer = target?;
+            Ok(())
+        }),
+        Parameter::Value(_) => Box::new(move |computer| {
+            computer.instruction_pointer = next;
+            Ok(())
+        }),
+        Parameter::Register(r) => Box::new(move |computer| {
+            computer.instruction_pointer = if taken(computer.registers[r]) {
+                target?
+            } else {
+                next
+            };
+            Ok(())
+        }),
+    }
+}
+
+fn lower<T: Io>(pc: usize, instruction: Instruction, checked: bool) -> Op<T> {
+    let next = pc + 1;
+    match instruction {
+        Instruction::Set(r, Parameter::Value(v)) => Box::new(move |computer| {
+            computer.write(r, v);
+            computer.instruction_pointer = next;
+            Ok(())
+        }),
+        Instruction::Add(r, p) if checked => binary(r, p, next, |a, b| {
+            a.checked_add(b).ok_or(ExecError::Overflow)
+        }),
+        Instruction::Sub(r, p) if checked => binary(r, p, next, |a, b| {
+            a.checked_sub(b).ok_or(ExecError::Overflow)
+        }),
+        Instruction::Multiply(r, p) if checked => binary(r, p, next, |a, b| {
+            a.checked_mul(b).ok_or(ExecError::Overflow)
+        }),
+        Instruction::Modulo(r, p) if checked => binary(r, p, next, |a, b| match b {
+            0 => Err(ExecError::DivisionByZero),
+            _ => a.checked_rem(b).ok_or(ExecError::Overflow),
+        }),
+        Instruction::JumpGreaterThanZero(x, Parameter::Value(offset)) => {
+            branch(x, target(pc, offset), next, |v| v > 0)
+        }
+        Instruction::JumpIfNotZero(x, Parameter::Value(offset)) => {
+            branch(x, target(pc, offset), next, |v| v != 0)
+        }
+        _ => Box::new(move |computer| computer.execute(instruction)),
+    }
+}
+
+impl<T: Io> Compiled<T> {
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// Like `Computer::run_until_halt`, with the same result.
+    ///
+    /// If the arithmetic or modulo mode of `computer` changed since it was
+    /// compiled, this simply runs the interpreter.
+    ///
+    /// # Panics
+    ///
+    /// If `computer` holds another program than the one compiled.
+    pub fn run(&self, computer: &mut Computer<T>, step_limit: Option<usize>) -> Halt {
+        assert!(
+            computer.instructions == self.instructions,
+            "The computer does not run the compiled program."
+        );
+        if (computer.arithmetic_mode, computer.modulo_mode)
+            != (self.arithmetic_mode, self.modulo_mode)
+        {
+            return computer.run_until_halt(step_limit);
+        }
+
+        // Nothing can enable the history or the tracer while this runs.
+        let recording = computer.is_recording();
+        let mut steps = 0;
+        let error = loop {
+            let op = match self.ops.get(computer.instruction_pointer) {
+                Some(op) => op,
+                None => break None,
+            };
+            if step_limit.is_some_and(|limit| steps >= limit) {
+                break Some(ExecError::StepLimitExhausted);
+            }
+            if computer.is_cancelled() {
+                break Some(ExecError::Cancelled);
+            }
+            let result = if recording {
+                computer.recorded(|computer| op(computer))
+            } else {
+                op(computer)
+            };
+            if let Err(error) = result {
+                break Some(error);
+            }
+            steps += 1;
+        };
+
+        Halt {
+            error,
+            steps,
+            instruction_pointer: computer.instruction_pointer,
+            registers: computer.registers,
+        }
+    }
+}
+
+impl<T: Io> Computer<T> {
+    /// Lower the program for `Compiled::run`, in the current arithmetic and
+    /// modulo modes.
+    pub fn compile(&self) -> Compiled<T> {
+        let checked = (self.arithmetic_mode, self.modulo_mode)
+            == (ArithmeticMode::Checked, ModuloMode::Truncated);
+
+        Compiled {
+            instructions: self.instructions.clone(),
+            ops: self
+                .instructions
+                .iter()
+                .enumerate()
+                .map(|(pc, &instruction)| lower(pc, instruction, checked))
+                .collect(),
+            arithmetic_mode: self.arithmetic_mode,
+            modulo_mode: self.modulo_mode,
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use super::profiler::{Branch, Profile};
+use super::{Instruction, Parameter};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Which instructions were executed, and which way every jump went, over
+/// any number of runs of the same program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    /// How many times each instruction was executed, indexed by program counter.
+    pub counts: Vec<usize>,
+    /// Outcomes of every jump instruction, indexed by program counter.
+    pub branches: BTreeMap<usize, Branch>,
+    /// For jumps on a constant, the only outcome there is.
+    constant: BTreeMap<usize, bool>,
+}
+
+impl Coverage {
+    /// Nothing covered yet.
+    pub fn new(instructions: &[Instruction]) -> Self {
+        let mut constant = BTreeMap::new();
+        for (pc, instruction) in instructions.iter().enumerate() {
+            if let Some((Parameter::Value(x), _)) = instruction.jump() {
+                if let Some(taken) = instruction.is_taken(x) {
+                    constant.insert(pc, taken);
+                }
+            }
+        }
+
+        Coverage {
+            counts: vec![0; instructions.len()],
+            branches: instructions
+                .iter()
+                .enumerate()
+                .filter(|(_, instruction)| instruction.is_jump())
+                .map(|(pc, _)| (pc, Branch::default()))
+                .collect(),
+            constant,
+        }
+    }
+
+    /// Add a run of the program.
+    ///
+    /// # Panics
+    ///
+    /// If `profile` is for a program of a different length.
+    pub fn add(&mut self, profile: &Profile) {
+        assert_eq!(
+            self.counts.len(),
+            profile.counts.len(),
+            "The profile is for another program."
+        );
+
+        for (count, added) in self.counts.iter_mut().zip(&profile.counts) {
+            *count += added;
+        }
+        for (pc, added) in &profile.branches {
+            let branch = self.branches.entry(*pc).or_default();
+            branch.taken += added.taken;
+            branch.not_taken += added.not_taken;
+        }
+    }
+
+    /// The outcomes a jump can have, with how many times each happened:
+    /// `(taken, count)`.
+    fn outcomes(&self, pc: usize) -> Vec<(bool, usize)> {
+        let branch = match self.branches.get(&pc) {
+            Some(branch) => branch,
+            None => return Vec::new(),
+        };
+        match self.constant.get(&pc) {
+            Some(true) => vec![(true, branch.taken)],
+            Some(false) => vec![(false, branch.not_taken)],
+            None => vec![(true, branch.taken), (false, branch.not_taken)],
+        }
+    }
+
+    /// Instructions executed at least once, out of how many.
+    pub fn lines(&self) -> (usize, usize) {
+        let hit = self.counts.iter().filter(|&&count| count > 0).count();
+        (hit, self.counts.len())
+    }
+
+    /// Branch outcomes seen at least once, out of how many are possible.
+    pub fn branch_outcomes(&self) -> (usize, usize) {
+        let outcomes: Vec<_> = self
+            .branches
+            .keys()
+            .flat_map(|&pc| self.outcomes(pc))
+            .collect();
+        let hit = outcomes.iter().filter(|&&(_, count)| count > 0).count();
+        (hit, outcomes.len())
+    }
+
+    /// The program, with execution counts in the margin (`#####` for
+    /// instructions never executed) and branch outcomes at the end of jump
+    /// lines, followed by a summary.
+    pub fn listing(&self, instructions: &[Instruction]) -> String {
+        let mut output = String::new();
+        for (pc, instruction) in instructions.iter().enumerate() {
+            match self.counts.get(pc).copied().unwrap_or_default() {
+                0 => output.push_str("       #####"),
+                count => {
+                    let _ = write!(output, "{:>12}", count);
+                }
+            }
+            let _ = write!(output, " {:>4}: {}", pc, instruction);
+
+            let outcomes: Vec<String> = self
+                .outcomes(pc)
+                .into_iter()
+                .map(|(taken, count)| {
+                    let outcome = if taken { "taken" } else { "not taken" };
+                    match count {
+                        0 => format!("{}: never", outcome),
+                        count => format!("{}: {}", outcome, count),
+                    }
+                })
+                .collect();
+            if !outcomes.is_empty() {
+                let _ = write!(output, "  [{}]", outcomes.join(", "));
+            }
+            output.push('\n');
+        }
+
+        let percent = |(hit, total): (usize, usize)| match total {
+            0 => 100.0,
+            total => 100.0 * hit as f64 / total as f64,
+        };
+        let (lines, branches) = (self.lines(), self.branch_outcomes());
+        let _ = writeln!(
+            output,
+            "\nInstructions: {}/{} ({:.1}%)",
+            lines.0,
+            lines.1,
+            percent(lines)
+        );
+        let _ = writeln!(
+            output,
+            "Branches: {}/{} ({:.1}%)",
+            branches.0,
+            branches.1,
+            percent(branches)
+        );
+
+        output
+    }
+
+    /// An LCOV tracefile for `source_file`, where `lines` holds the source
+    /// line of each instruction, as given by `assembler::line_numbers`.
+    pub fn lcov(&self, source_file: &str, lines: &[usize]) -> String {
+        let line = |pc: usize| lines.get(pc).copied().unwrap_or(pc + 1);
+        let mut output = String::new();
+        let _ = writeln!(output, "TN:");
+        let _ = writeln!(output, "SF:{}", source_file);
+
+        for &pc in self.branches.keys() {
+            for (idx, (_, count)) in self.outcomes(pc).into_iter().enumerate() {
+                // LCOV tells apart a branch never taken from one never reached.
+                let count = match self.counts[pc] {
+                    0 => "-".to_string(),
+                    _ => count.to_string(),
+                };
+                let _ = writeln!(output, "BRDA:{},0,{},{}", line(pc), idx, count);
+            }
+        }
+        let (hit, found) = self.branch_outcomes();
+        let _ = writeln!(output, "BRF:{}", found);
+        let _ = writeln!(output, "BRH:{}", hit);
+
+        for (pc, count) in self.counts.iter().enumerate() {
+            let _ = writeln!(output, "DA:{},{}", line(pc), count);
+        }
+        let (hit, found) = self.lines();
+        let _ = writeln!(output, "LF:{}", found);
+        let _ = writeln!(output, "LH:{}", hit);
+        let _ = writeln!(output, "end_of_record");
+
+        output
+    }
+}
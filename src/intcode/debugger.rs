@@ -0,0 +1,167 @@
+use super::io::{Channel, Io};
+use super::{Computer, ExecError, Instruction, Register, Semantics, Value};
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Event {
+    /// One instruction was executed, and nothing of note happened.
+    Stepped,
+    /// The instruction pointer reached a breakpoint.
+    Breakpoint(usize),
+    /// A watched register was written to.
+    Watchpoint {
+        register: Register,
+        old: Value,
+        new: Value,
+    },
+    Halted,
+    Error(ExecError),
+}
+
+/// Wraps a `Computer` to execute it one instruction at a time, or until
+/// something interesting happens.
+#[derive(Debug)]
+pub struct Debugger<T = Channel> {
+    computer: Computer<T>,
+    breakpoints: BTreeSet<usize>,
+    watchpoints: BTreeSet<Register>,
+    /// A breakpoint reached by a step that reported a watchpoint instead.
+    pending_breakpoint: Option<usize>,
+}
+
+impl<T: Io> Debugger<T> {
+    pub fn new(computer: Computer<T>) -> Self {
+        Debugger {
+            computer,
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeSet::new(),
+            pending_breakpoint: None,
+        }
+    }
+
+    pub fn computer(&self) -> &Computer<T> {
+        &self.computer
+    }
+
+    pub fn computer_mut(&mut self) -> &mut Computer<T> {
+        &mut self.computer
+    }
+
+    pub fn into_computer(self) -> Computer<T> {
+        self.computer
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = usize> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    /// Returns `false` if the breakpoint was already set.
+    pub fn add_breakpoint(&mut self, pc: usize) -> bool {
+        self.breakpoints.insert(pc)
+    }
+
+    /// Returns `false` if there was no such breakpoint.
+    pub fn remove_breakpoint(&mut self, pc: usize) -> bool {
+        self.breakpoints.remove(&pc)
+    }
+
+    pub fn watchpoints(&self) -> impl Iterator<Item = Register> + '_ {
+        self.watchpoints.iter().copied()
+    }
+
+    /// Returns `false` if the watchpoint was already set.
+    pub fn add_watchpoint(&mut self, register: Register) -> bool {
+        self.watchpoints.insert(register)
+    }
+
+    /// Returns `false` if there was no such watchpoint.
+    pub fn remove_watchpoint(&mut self, register: Register) -> bool {
+        self.watchpoints.remove(&register)
+    }
+
+    /// The registers the current instruction may be about to write to.
+    fn destinations(&self) -> Vec<Register> {
+        match self.computer.current_instruction() {
+            // Only recovers a frequency.
+            Some(Instruction::Receive(_)) if self.computer.semantics() == Semantics::Sound => {
+                Vec::new()
+            }
+            Some(Instruction::Fused(fused)) => fused.writes(),
+            Some(instruction) => instruction.destination().into_iter().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Execute exactly one instruction.
+    ///
+    /// When a superinstruction writes to several watched registers, only the
+    /// first one whose value changed is reported.
+    pub fn step(&mut self) -> Event {
+        self.pending_breakpoint = None;
+        if self.computer.is_halted() {
+            return Event::Halted;
+        }
+
+        let fused = matches!(
+            self.computer.current_instruction(),
+            Some(Instruction::Fused(_))
+        );
+        let watched: Vec<(Register, Value)> = self
+            .destinations()
+            .into_iter()
+            .filter(|r| self.watchpoints.contains(r))
+            .map(|r| (r, self.computer.register(r)))
+            .collect();
+
+        if let Err(error) = self.computer.step() {
+            return Event::Error(error);
+        }
+
+        let changed = watched
+            .iter()
+            .copied()
+            .find(|&(register, old)| self.computer.register(register) != old);
+        // A plain instruction always writes its destination, even when the
+        // value stays the same.
+        let written = changed.or(watched.first().copied().filter(|_| !fused));
+        if let Some((register, old)) = written {
+            let pc = self.computer.instruction_pointer();
+            if self.breakpoints.contains(&pc) {
+                self.pending_breakpoint = Some(pc);
+            }
+            return Event::Watchpoint {
+                register,
+                old,
+                new: self.computer.register(register),
+            };
+        }
+
+        if self.computer.is_halted() {
+            Event::Halted
+        } else if self
+            .breakpoints
+            .contains(&self.computer.instruction_pointer())
+        {
+            Event::Breakpoint(self.computer.instruction_pointer())
+        } else {
+            Event::Stepped
+        }
+    }
+
+    /// Keep stepping until anything other than `Event::Stepped` happens. Always
+    /// executes at least one instruction, so continuing from a breakpoint does
+    /// not immediately stop on it again, unless that breakpoint was reached
+    /// by a step that reported a watchpoint.
+    pub fn resume(&mut self) -> Event {
+        let pending = self.pending_breakpoint.take();
+        if let Some(pc) = pending.filter(|&pc| pc == self.computer.instruction_pointer()) {
+            return Event::Breakpoint(pc);
+        }
+        loop {
+            match self.step() {
+                Event::Stepped => (),
+                event => return event,
+            }
+        }
+    }
+}
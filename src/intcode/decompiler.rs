@@ -0,0 +1,417 @@
+use super::dialect::Behavior;
+use super::{Instruction, Parameter, Register, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+
+const INDENT: &str = "    ";
+
+/// A jump, as far as the decompiler is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Jump {
+    /// `None` if the jump is always taken.
+    condition: Option<Condition>,
+    target: Target,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Instruction(usize),
+    /// Outside of the program, which halts it.
+    Halt,
+    /// Held in a register, only known at runtime.
+    Dynamic(Parameter),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Condition {
+    parameter: Parameter,
+    kind: ConditionKind,
+    negated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConditionKind {
+    GreaterThanZero,
+    NotZero,
+    /// An extra opcode, shown as a call.
+    Extended(&'static str),
+}
+
+impl Condition {
+    fn negate(self) -> Self {
+        Condition {
+            negated: !self.negated,
+            ..self
+        }
+    }
+}
+
+/// Turns a program into structured pseudocode, recognizing the loops and
+/// if-statements hidden behind the jumps. Anything that does not fit a known
+/// shape is left as a `goto`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decompiler {
+    instructions: Vec<Instruction>,
+    names: HashMap<Register, String>,
+}
+
+impl Decompiler {
+    pub fn new(instructions: &[Instruction]) -> Self {
+        Decompiler {
+            instructions: instructions.to_vec(),
+            names: HashMap::new(),
+        }
+    }
+
+    /// Use a more meaningful name than the register's letter.
+    pub fn name(mut self, register: Register, name: &str) -> Self {
+        self.names.insert(register, name.to_string());
+        self
+    }
+
+    pub fn decompile(&self) -> String {
+        // The labels can only be known once every `goto` has been found, so
+        // this is done twice.
+        let mut labels = BTreeSet::new();
+        let mut output = String::new();
+        self.region(0, self.instructions.len(), 0, &mut labels, &mut output);
+
+        let mut output = String::new();
+        self.region(0, self.instructions.len(), 0, &mut labels, &mut output);
+        output
+    }
+
+    fn register(&self, register: Register) -> String {
+        self.names
+            .get(&register)
+            .cloned()
+            .unwrap_or_else(|| register.to_string())
+    }
+
+    fn parameter(&self, parameter: Parameter) -> String {
+        match parameter {
+            Parameter::Value(v) => v.to_string(),
+            Parameter::Register(r) => self.register(r),
+        }
+    }
+
+    fn condition(&self, condition: Condition) -> String {
+        let operator = match (condition.kind, condition.negated) {
+            (ConditionKind::GreaterThanZero, false) => ">",
+            (ConditionKind::GreaterThanZero, true) => "<=",
+            (ConditionKind::NotZero, false) => "!=",
+            (ConditionKind::NotZero, true) => "==",
+            (ConditionKind::Extended(mnemonic), negated) => {
+                let call = format!("{}({})", mnemonic, self.parameter(condition.parameter));
+                return if negated { format!("!{}", call) } else { call };
+            }
+        };
+        format!("{} {} 0", self.parameter(condition.parameter), operator)
+    }
+
+    fn jump(&self, pc: usize) -> Option<Jump> {
+        let instruction = self.instructions[pc];
+        let (parameter, offset) = instruction.jump()?;
+        let kind = match instruction {
+            Instruction::JumpGreaterThanZero(_, _) => ConditionKind::GreaterThanZero,
+            Instruction::JumpIfNotZero(_, _) => ConditionKind::NotZero,
+            _ => ConditionKind::Extended(instruction.mnemonic()),
+        };
+
+        let condition = match parameter {
+            Parameter::Value(v) if instruction.is_taken(v) == Some(true) => None,
+            _ => Some(Condition {
+                parameter,
+                kind,
+                negated: false,
+            }),
+        };
+
+        let target = match offset {
+            Parameter::Value(offset) => (pc as Value)
+                .checked_add(offset)
+                .and_then(|target| usize::try_from(target).ok())
+                .filter(|&target| target < self.instructions.len())
+                .map_or(Target::Halt, Target::Instruction),
+            Parameter::Register(_) => Target::Dynamic(offset),
+        };
+
+        Some(Jump { condition, target })
+    }
+
+    /// Whether the jump at `pc` can never be taken, in which case it is a
+    /// no-op.
+    fn never_taken(&self, pc: usize) -> bool {
+        let instruction = self.instructions[pc];
+        matches!(
+            instruction.jump(),
+            Some((Parameter::Value(v), _)) if instruction.is_taken(v) == Some(false)
+        )
+    }
+
+    fn statement(&self, instruction: Instruction) -> String {
+        let compound = |r: Register, operator: &str, p: Parameter| {
+            format!("{} {}= {}", self.register(r), operator, self.parameter(p))
+        };
+
+        match instruction {
+            Instruction::Send(p) => format!("send({})", self.parameter(p)),
+            Instruction::Set(r, p) => format!("{} = {}", self.register(r), self.parameter(p)),
+            // `sub x -1` is how these programs increment.
+            Instruction::Add(r, Parameter::Value(v)) | Instruction::Sub(r, Parameter::Value(v))
+                if v < 0 && v != Value::MIN =>
+            {
+                let operator = if matches!(instruction, Instruction::Add(_, _)) {
+                    "-"
+                } else {
+                    "+"
+                };
+                compound(r, operator, Parameter::Value(-v))
+            }
+            Instruction::Add(r, p) => compound(r, "+", p),
+            Instruction::Sub(r, p) => compound(r, "-", p),
+            Instruction::Multiply(r, p) => compound(r, "*", p),
+            Instruction::Modulo(r, p) => compound(r, "%", p),
+            Instruction::Receive(r) => format!("{} = receive()", self.register(r)),
+            Instruction::Fused(fused) => fused.to_string(),
+            Instruction::Extended(opcode, x, y) => match opcode.behavior {
+                Behavior::Arithmetic(_) | Behavior::Division(_) => format!(
+                    "{} = {}({}, {})",
+                    self.parameter(x),
+                    opcode.mnemonic,
+                    self.parameter(x),
+                    self.parameter(y)
+                ),
+                Behavior::Send => format!("{}({})", opcode.mnemonic, self.parameter(x)),
+                Behavior::Jump(_) => unreachable!("Jumps are never plain statements."),
+            },
+            Instruction::JumpGreaterThanZero(_, _) | Instruction::JumpIfNotZero(_, _) => {
+                unreachable!("Jumps are never plain statements.")
+            }
+        }
+    }
+
+    fn goto(&self, target: Target, labels: &mut BTreeSet<usize>) -> String {
+        match target {
+            Target::Instruction(target) => {
+                labels.insert(target);
+                format!("goto L{}", target)
+            }
+            Target::Halt => "halt".to_string(),
+            Target::Dynamic(offset) => format!("goto pc + {}", self.parameter(offset)),
+        }
+    }
+
+    /// Whether jumping to `target` leaves the loop ending at `q`.
+    fn exits(&self, target: Target, q: usize) -> bool {
+        match target {
+            Target::Instruction(target) => target == q + 1,
+            Target::Halt => q + 1 == self.instructions.len(),
+            Target::Dynamic(_) => false,
+        }
+    }
+
+    /// For a loop from `pc` to an unconditional jump back at `q`, the
+    /// condition to stay in it, checked before every iteration, and where its
+    /// body starts. Either one jump leaves the loop:
+    /// ```text
+    /// pc:     jgz x <after q>
+    /// ```
+    /// or, since jumps only happen when their condition holds, two:
+    /// ```text
+    /// pc:     jnz x 2
+    /// pc + 1: jnz 1 <after q>
+    /// ```
+    fn loop_guard(&self, pc: usize, q: usize) -> Option<(Condition, usize)> {
+        let (condition, target) = match self.jump(pc)? {
+            Jump {
+                condition: Some(condition),
+                target,
+            } => (condition, target),
+            _ => return None,
+        };
+        if self.exits(target, q) {
+            return Some((condition.negate(), pc + 1));
+        }
+        match self.jump(pc + 1) {
+            Some(Jump {
+                condition: None,
+                target: exit,
+            }) if target == Target::Instruction(pc + 2) && pc + 2 <= q && self.exits(exit, q) => {
+                Some((condition, pc + 2))
+            }
+            _ => None,
+        }
+    }
+
+    /// The last backward jump in `pc..end` that goes back to `pc`, if any.
+    fn loop_end(&self, pc: usize, end: usize) -> Option<usize> {
+        (pc..end)
+            .rev()
+            .find(|&q| matches!(self.jump(q), Some(jump) if jump.target == Target::Instruction(pc)))
+    }
+
+    fn region(
+        &self,
+        start: usize,
+        end: usize,
+        depth: usize,
+        labels: &mut BTreeSet<usize>,
+        output: &mut String,
+    ) {
+        let indent = INDENT.repeat(depth);
+        let mut pc = start;
+
+        while pc < end {
+            if labels.contains(&pc) {
+                let _ = writeln!(output, "L{}:", pc);
+            }
+
+            // while (condition) { ... }
+            if let Some(q) = self.loop_end(pc, end) {
+                let condition = self.jump(q).and_then(|jump| jump.condition);
+                let guard = match condition {
+                    None => self.loop_guard(pc, q),
+                    Some(_) => None,
+                };
+                if let Some((guard, body)) = guard {
+                    let _ = writeln!(output, "{}while {} {{", indent, self.condition(guard));
+                    self.region(body, q, depth + 1, labels, output);
+                    // Jumping to the end of the loop means checking its condition.
+                    if labels.contains(&q) {
+                        let _ = writeln!(output, "L{}:", q);
+                    }
+                    let _ = writeln!(output, "{}}}", indent);
+                    // And jumping to the jump out of it means leaving it.
+                    if body == pc + 2 && labels.contains(&(pc + 1)) {
+                        let _ = writeln!(output, "L{}:", pc + 1);
+                    }
+                    pc = q + 1;
+                    continue;
+                }
+            }
+
+            // do { ... } while (condition)
+            if let Some(q) = self.loop_end(pc, end) {
+                let condition = self.jump(q).and_then(|jump| jump.condition);
+                let _ = writeln!(
+                    output,
+                    "{}{}",
+                    indent,
+                    if condition.is_some() {
+                        "do {"
+                    } else {
+                        "loop {"
+                    }
+                );
+                self.region(pc, q, depth + 1, labels, output);
+                // Jumping to the end of the loop means checking its condition.
+                if labels.contains(&q) {
+                    let _ = writeln!(output, "L{}:", q);
+                }
+                match condition {
+                    Some(condition) => {
+                        let _ =
+                            writeln!(output, "{}}} while {}", indent, self.condition(condition));
+                    }
+                    None => {
+                        let _ = writeln!(output, "{}}}", indent);
+                    }
+                }
+                pc = q + 1;
+                continue;
+            }
+
+            if self.never_taken(pc) {
+                pc += 1;
+                continue;
+            }
+
+            match self.jump(pc) {
+                None => {
+                    let _ = writeln!(
+                        output,
+                        "{}{}",
+                        indent,
+                        self.statement(self.instructions[pc])
+                    );
+                    pc += 1;
+                }
+                // if (!condition) { ... }, possibly followed by an else.
+                Some(Jump {
+                    condition: Some(condition),
+                    target: Target::Instruction(target),
+                }) if target > pc + 1 && target <= end => {
+                    let else_end = match self.jump(target - 1) {
+                        Some(Jump {
+                            condition: None,
+                            target: Target::Instruction(after),
+                        }) if after > target && after <= end => Some(after),
+                        _ => None,
+                    };
+
+                    match else_end {
+                        // Nothing to do in the "then" part, so only the "else"
+                        // remains, with the original condition.
+                        Some(after) if target - 1 == pc + 1 => {
+                            let _ =
+                                writeln!(output, "{}if {} {{", indent, self.condition(condition));
+                            self.region(target, after, depth + 1, labels, output);
+                            let _ = writeln!(output, "{}}}", indent);
+                            // Where the "then" part jumps over the "else".
+                            if labels.contains(&(pc + 1)) {
+                                let _ = writeln!(output, "L{}:", pc + 1);
+                            }
+                            pc = after;
+                        }
+                        Some(after) => {
+                            let _ = writeln!(
+                                output,
+                                "{}if {} {{",
+                                indent,
+                                self.condition(condition.negate())
+                            );
+                            self.region(pc + 1, target - 1, depth + 1, labels, output);
+                            if labels.contains(&(target - 1)) {
+                                let _ = writeln!(output, "L{}:", target - 1);
+                            }
+                            let _ = writeln!(output, "{}}} else {{", indent);
+                            self.region(target, after, depth + 1, labels, output);
+                            let _ = writeln!(output, "{}}}", indent);
+                            pc = after;
+                        }
+                        None => {
+                            let _ = writeln!(
+                                output,
+                                "{}if {} {{",
+                                indent,
+                                self.condition(condition.negate())
+                            );
+                            self.region(pc + 1, target, depth + 1, labels, output);
+                            let _ = writeln!(output, "{}}}", indent);
+                            pc = target;
+                        }
+                    }
+                }
+                Some(Jump { condition, target }) => {
+                    let goto = self.goto(target, labels);
+                    match condition {
+                        Some(condition) => {
+                            let _ = writeln!(
+                                output,
+                                "{}if {} {{ {} }}",
+                                indent,
+                                self.condition(condition),
+                                goto
+                            );
+                        }
+                        None => {
+                            let _ = writeln!(output, "{}{}", indent, goto);
+                        }
+                    }
+                    pc += 1;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use std::str::FromStr;
+
+use super::{parameter_at, register_at, Instruction, Parameter, ParseError, Value};
+
+/// Every built-in operation, whatever the puzzle it comes from.
+const BUILTINS: [&str; 9] = [
+    "snd", "set", "add", "sub", "mul", "mod", "rcv", "jgz", "jnz",
+];
+
+/// What an extra opcode does, with its operands as `X` and `Y`.
+#[derive(Debug, Clone, Copy)]
+pub enum Behavior {
+    /// `op X Y` sets register X to `f(X, Y)`, where `None` is an overflow.
+    Arithmetic(fn(Value, Value) -> Option<Value>),
+    /// Like `Arithmetic`, but a Y of zero is a division by zero.
+    Division(fn(Value, Value) -> Option<Value>),
+    /// `op X Y` jumps by an offset of Y if `f(X)`.
+    Jump(fn(Value) -> bool),
+    /// `op X` does whatever `snd X` does.
+    Send,
+}
+
+/// An opcode that is not built into `Instruction`. Opcodes are told apart by
+/// their mnemonic only.
+#[derive(Debug, Clone, Copy)]
+pub struct Opcode {
+    pub mnemonic: &'static str,
+    pub behavior: Behavior,
+}
+
+impl PartialEq for Opcode {
+    fn eq(&self, other: &Self) -> bool {
+        self.mnemonic == other.mnemonic
+    }
+}
+
+impl Eq for Opcode {}
+
+impl std::hash::Hash for Opcode {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.mnemonic.hash(state);
+    }
+}
+
+impl Opcode {
+    /// How many operands it takes.
+    pub fn arity(&self) -> usize {
+        match self.behavior {
+            Behavior::Send => 1,
+            _ => 2,
+        }
+    }
+}
+
+/// `div X Y`: X = X / Y, rounded towards zero.
+pub static DIV: Opcode = Opcode {
+    mnemonic: "div",
+    behavior: Behavior::Division(Value::checked_div),
+};
+
+/// `jlz X Y`: jump by Y if X is negative.
+pub static JLZ: Opcode = Opcode {
+    mnemonic: "jlz",
+    behavior: Behavior::Jump(Value::is_negative),
+};
+
+/// `out X`: the same as `snd X`.
+pub static OUT: Opcode = Opcode {
+    mnemonic: "out",
+    behavior: Behavior::Send,
+};
+
+/// Every extra opcode that comes with this crate.
+pub static EXTRAS: [&Opcode; 3] = [&DIV, &JLZ, &OUT];
+
+/// Which operations a program may use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dialect {
+    builtins: Vec<&'static str>,
+    extensions: Vec<&'static Opcode>,
+}
+
+/// The permissive dialect.
+impl Default for Dialect {
+    fn default() -> Self {
+        Dialect::permissive()
+    }
+}
+
+impl Dialect {
+    fn builtins(mnemonics: &[&'static str]) -> Self {
+        Dialect {
+            builtins: mnemonics.to_vec(),
+            extensions: Vec::new(),
+        }
+    }
+
+    /// Every operation there is: the built-in ones from both puzzles, and
+    /// every opcode in `EXTRAS`.
+    pub fn permissive() -> Self {
+        EXTRAS
+            .iter()
+            .fold(Dialect::strict(), |dialect, opcode| dialect.with(opcode))
+    }
+
+    /// Day 18: `snd`, `set`, `add`, `mul`, `mod`, `rcv` and `jgz`.
+    pub fn duet() -> Self {
+        Dialect::builtins(&["snd", "set", "add", "mul", "mod", "rcv", "jgz"])
+    }
+
+    /// Day 23: `set`, `sub`, `mul` and `jnz`.
+    pub fn coprocessor() -> Self {
+        Dialect::builtins(&["set", "sub", "mul", "jnz"])
+    }
+
+    /// Every operation from the 2017 puzzles, and none of the `EXTRAS`.
+    pub fn strict() -> Self {
+        Dialect::builtins(&BUILTINS)
+    }
+
+    /// Also accept `opcode`, replacing any operation with the same mnemonic.
+    pub fn with(mut self, opcode: &'static Opcode) -> Self {
+        self = self.without(opcode.mnemonic);
+        self.extensions.push(opcode);
+        self
+    }
+
+    /// Stop accepting an operation, built-in or not.
+    pub fn without(mut self, mnemonic: &str) -> Self {
+        self.builtins.retain(|&builtin| builtin != mnemonic);
+        self.extensions.retain(|opcode| opcode.mnemonic != mnemonic);
+        self
+    }
+
+    pub fn allows(&self, mnemonic: &str) -> bool {
+        self.builtins.contains(&mnemonic) || self.extension(mnemonic).is_some()
+    }
+
+    fn extension(&self, mnemonic: &str) -> Option<&'static Opcode> {
+        self.extensions
+            .iter()
+            .copied()
+            .find(|opcode| opcode.mnemonic == mnemonic)
+    }
+
+    /// Whether `mnemonic` is a jump, whose offset can be a label.
+    pub fn is_jump(&self, mnemonic: &str) -> bool {
+        match self.extension(mnemonic) {
+            Some(opcode) => matches!(opcode.behavior, Behavior::Jump(_)),
+            None => matches!(mnemonic, "jgz" | "jnz") && self.allows(mnemonic),
+        }
+    }
+
+    /// Same as `Instruction::parse_words`, but with this dialect's operations.
+    pub fn parse_words(&self, words: &[&str]) -> Result<Instruction, (usize, ParseError)> {
+        let op = *words.first().ok_or((0, ParseError::MissingOperation))?;
+
+        let opcode = match self.extension(op) {
+            Some(opcode) => opcode,
+            None if self.builtins.contains(&op) => return Instruction::parse_words(words),
+            None if BUILTINS.contains(&op) => {
+                return Err((0, ParseError::NotAllowed(op.to_string())))
+            }
+            None => return Err((0, ParseError::UnknownOperation(op.to_string()))),
+        };
+
+        let x = match opcode.behavior {
+            Behavior::Arithmetic(_) | Behavior::Division(_) => {
+                Parameter::Register(register_at(words, 1)?)
+            }
+            Behavior::Jump(_) | Behavior::Send => parameter_at(words, 1)?,
+        };
+        let y = match opcode.arity() {
+            1 => Parameter::Value(0),
+            _ => parameter_at(words, 2)?,
+        };
+
+        match words.get(opcode.arity() + 1) {
+            Some(extra) => Err((
+                opcode.arity() + 1,
+                ParseError::UnexpectedParameter(extra.to_string()),
+            )),
+            None => Ok(Instruction::Extended(opcode, x, y)),
+        }
+    }
+
+    pub fn parse(&self, s: &str) -> Result<Instruction, ParseError> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        self.parse_words(&words).map_err(|(_, error)| error)
+    }
+}
+
+/// A dialect name, optionally followed by extra opcodes from `EXTRAS`, such
+/// as `coprocessor+div+jlz`.
+impl FromStr for Dialect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut names = s.trim().split('+');
+        let mut dialect = match names.next().unwrap_or_default() {
+            "permissive" => Dialect::permissive(),
+            "duet" => Dialect::duet(),
+            "coprocessor" => Dialect::coprocessor(),
+            "strict" => Dialect::strict(),
+            name => return Err(format!("Unknown dialect: {}", name)),
+        };
+
+        for name in names {
+            let opcode = EXTRAS
+                .iter()
+                .find(|opcode| opcode.mnemonic == name)
+                .ok_or_else(|| format!("Unknown opcode: {}", name))?;
+            dialect = dialect.with(opcode);
+        }
+
+        Ok(dialect)
+    }
+}
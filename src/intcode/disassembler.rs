@@ -0,0 +1,60 @@
+use super::{Instruction, Parameter, Value};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// Where a jump lands, if its offset is known.
+fn target(pc: usize, instruction: Instruction) -> Option<Value> {
+    match instruction.jump()? {
+        (_, Parameter::Value(offset)) => (pc as Value).checked_add(offset),
+        _ => None,
+    }
+}
+
+/// A numbered listing of the program. Every instruction a jump can land on
+/// gets a label, and jumps with a constant offset show their absolute target:
+/// ```text
+///       L2:
+///    2: sub a 1
+///    3: jnz a -1      # -> L2
+///    4: jgz 1 10      # -> 14 (exit)
+/// ```
+/// Jumping right past the last instruction is labelled like any other target,
+/// while jumps further out only show where they would land.
+pub fn disassemble(instructions: &[Instruction]) -> String {
+    let len = instructions.len() as Value;
+    let labels: BTreeSet<usize> = instructions
+        .iter()
+        .enumerate()
+        .filter_map(|(pc, &instruction)| target(pc, instruction))
+        .filter(|target| (0..=len).contains(target))
+        .map(|target| target as usize)
+        .collect();
+    let width = instructions
+        .iter()
+        .map(|instruction| instruction.to_string().len())
+        .max()
+        .unwrap_or_default();
+
+    let mut output = String::new();
+    for (pc, &instruction) in instructions.iter().enumerate() {
+        if labels.contains(&pc) {
+            let _ = writeln!(output, "      L{}:", pc);
+        }
+        let line = format!("{:>4}: {}", pc, instruction);
+        let comment = match target(pc, instruction) {
+            None => {
+                let _ = writeln!(output, "{}", line);
+                continue;
+            }
+            Some(target) if target < 0 => format!("{} (invalid)", target),
+            Some(target) if target > len => format!("{} (exit)", target),
+            Some(target) => format!("L{}", target),
+        };
+        let _ = writeln!(output, "{:<w$}  # -> {}", line, comment, w = width + 6);
+    }
+    if labels.contains(&instructions.len()) {
+        let _ = writeln!(output, "      L{}:", instructions.len());
+    }
+
+    output
+}
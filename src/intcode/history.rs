@@ -0,0 +1,235 @@
+use super::io::{Io, IoError};
+use super::{Computer, ExecError, Instruction, Register, Semantics, Value};
+use std::collections::VecDeque;
+
+/// What a single step changed, so that it can be undone.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) struct Change {
+    instruction_pointer: usize,
+    /// Registers written to, with the value they had before.
+    writes: Vec<(Register, Value)>,
+    sent: usize,
+    last_played: Value,
+    recovered: Option<Value>,
+    /// Goes back to the input when this is undone.
+    received: Option<Value>,
+    /// Every big value from before, since writes only hold plain values.
+    #[cfg(feature = "bigint")]
+    big: std::collections::BTreeMap<Register, num_bigint::BigInt>,
+}
+
+impl Change {
+    fn writes_to(&self, register: Register) -> bool {
+        self.writes.iter().any(|&(r, _)| r == register)
+    }
+}
+
+/// The last steps, oldest first.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) struct History {
+    changes: VecDeque<Change>,
+    /// How many steps to keep, if not all of them.
+    limit: Option<usize>,
+}
+
+impl History {
+    fn push(&mut self, change: Change) {
+        if self.limit.is_some_and(|limit| self.changes.len() >= limit) {
+            self.changes.pop_front();
+        }
+        if self.limit != Some(0) {
+            self.changes.push_back(change);
+        }
+    }
+}
+
+impl<T: Io> Computer<T> {
+    /// Start recording every step, so they can be undone later.
+    pub fn enable_history(&mut self) {
+        self.set_history_limit(None);
+    }
+
+    /// Start recording steps, but only keep the last `limit` of them. Also
+    /// changes the limit if the history is already enabled.
+    pub fn enable_bounded_history(&mut self, limit: usize) {
+        self.set_history_limit(Some(limit));
+    }
+
+    fn set_history_limit(&mut self, limit: Option<usize>) {
+        let history = self.history.get_or_insert_with(|| History {
+            changes: VecDeque::new(),
+            limit,
+        });
+        history.limit = limit;
+        if let Some(limit) = limit {
+            let excess = history.changes.len().saturating_sub(limit);
+            history.changes.drain(..excess);
+        }
+    }
+
+    /// How many steps the history keeps, if it is enabled: `Some(None)`
+    /// when it keeps them all.
+    pub fn history_limit(&self) -> Option<Option<usize>> {
+        self.history.as_ref().map(|history| history.limit)
+    }
+
+    /// Stop recording, and forget everything recorded so far.
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    pub fn clear_history(&mut self) {
+        if let Some(history) = self.history.as_mut() {
+            history.changes.clear();
+        }
+    }
+
+    /// Whether steps go through `recorded`, for the history or the tracer.
+    pub(super) fn is_recording(&self) -> bool {
+        self.history.is_some() || self.tracer.is_some()
+    }
+
+    /// How many steps can be undone.
+    pub fn history_len(&self) -> usize {
+        self.history
+            .as_ref()
+            .map_or(0, |history| history.changes.len())
+    }
+
+    /// Execute `f`, recording what it changed if the history or the tracer
+    /// is enabled.
+    pub(super) fn recorded(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<(), ExecError>,
+    ) -> Result<(), ExecError> {
+        if !self.is_recording() {
+            return f(self);
+        }
+
+        let registers = self.registers;
+        let instruction_pointer = self.instruction_pointer;
+        let sent = self.sent;
+        let last_played = self.last_played;
+        let recovered = self.recovered;
+        #[cfg(feature = "bigint")]
+        let big = self.big.clone();
+        let sending = self.sending();
+
+        f(self)?;
+
+        // A superinstruction that could not run executed the first of its
+        // original instructions instead.
+        let instruction = match self.instructions[instruction_pointer] {
+            Instruction::Fused(fused)
+                if self.instruction_pointer != instruction_pointer + fused.span() =>
+            {
+                fused.first()
+            }
+            instruction => instruction,
+        };
+        let (destination, received) = match (self.semantics, instruction) {
+            (Semantics::Duet, Instruction::Receive(r)) => (Some(r), Some(self.registers[r])),
+            (Semantics::Sound, Instruction::Receive(_)) => (None, None),
+            _ => (instruction.destination(), None),
+        };
+        // Superinstructions write to several registers, so looking for
+        // changes catches those as well.
+        let changed: Vec<Register> = registers
+            .iter()
+            .filter(|&(register, value)| {
+                self.registers[register] != value || Some(register) == destination
+            })
+            .map(|(register, _)| register)
+            .collect();
+
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.record(
+                instruction_pointer,
+                instruction,
+                changed.iter().map(|&r| (r, self.registers[r])).collect(),
+                sending.filter(|_| self.sent > sent),
+                received,
+            );
+        }
+        if let Some(history) = self.history.as_mut() {
+            history.push(Change {
+                instruction_pointer,
+                writes: changed.iter().map(|&r| (r, registers[r])).collect(),
+                sent,
+                last_played,
+                recovered,
+                received,
+                #[cfg(feature = "bigint")]
+                big,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Undo the last step. Returns `false` if there is nothing to undo.
+    ///
+    /// A value that was received is put back in front of the input, but a
+    /// value that was sent cannot be taken back. If that fails, nothing is
+    /// undone.
+    pub fn step_back(&mut self) -> Result<bool, IoError> {
+        let len = self.history_len();
+        self.rewind_to(len.saturating_sub(1)).map(|()| len > 0)
+    }
+
+    /// Undo every step from the `index`th one in the history on, putting all
+    /// they received back in the input first, so that either everything is
+    /// undone or nothing is.
+    fn rewind_to(&mut self, index: usize) -> Result<(), IoError> {
+        let history = match self.history.as_mut() {
+            Some(history) if index < history.changes.len() => history,
+            _ => return Ok(()),
+        };
+
+        let received: Vec<Value> = history
+            .changes
+            .range(index..)
+            .filter_map(|change| change.received)
+            .collect();
+        if !received.is_empty() {
+            let pending = self.io.pending()?;
+            self.io.set_pending(&[received, pending].concat())?;
+        }
+
+        let undone: Vec<Change> = history.changes.drain(index..).rev().collect();
+        for change in undone {
+            for (register, value) in change.writes {
+                self.write(register, value);
+            }
+            #[cfg(feature = "bigint")]
+            {
+                self.big = change.big;
+            }
+            self.instruction_pointer = change.instruction_pointer;
+            self.sent = change.sent;
+            self.last_played = change.last_played;
+            self.recovered = change.recovered;
+        }
+
+        Ok(())
+    }
+
+    /// Undo steps until right before the last one that wrote to `register`.
+    /// Returns the program counter of that instruction, or `None` (having
+    /// undone nothing) if the history holds no such write.
+    pub fn rewind_to_write(&mut self, register: Register) -> Result<Option<usize>, IoError> {
+        let index = self.history.as_ref().and_then(|history| {
+            history
+                .changes
+                .iter()
+                .rposition(|change| change.writes_to(register))
+        });
+        match index {
+            Some(index) => {
+                self.rewind_to(index)?;
+                Ok(Some(self.instruction_pointer))
+            }
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,768 @@
+use super::analysis::ControlFlowGraph;
+use super::dialect::Behavior;
+use super::optimizer::{optimize, Fused};
+use super::registers::NBR_REGISTERS;
+use super::{Instruction, Parameter, Register, Value};
+use std::collections::BTreeSet;
+use std::fmt::{Display, Write};
+
+/// How many times the ranges before an instruction can grow before they are
+/// widened to infinity, which is what guarantees that the analysis ends.
+const WIDEN_AFTER: usize = 3;
+/// Passes made after widening, to win back some of the precision it lost.
+const NARROWING_PASSES: usize = 2;
+
+/// Every value from `min` to `max`, both included. `Value::MIN` and
+/// `Value::MAX` stand for unbounded, and arithmetic never makes them finite
+/// again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Interval {
+    pub min: Value,
+    pub max: Value,
+}
+
+fn clamp(value: i128) -> Value {
+    value.clamp(Value::MIN.into(), Value::MAX.into()) as Value
+}
+
+/// Far enough out that adding any value leaves it out of range.
+const INFINITY: i128 = 1 << 100;
+
+/// A bound as a number, with infinities for the unbounded ends.
+fn extended(bound: Value) -> i128 {
+    match bound {
+        Value::MIN => -INFINITY,
+        Value::MAX => INFINITY,
+        bound => bound.into(),
+    }
+}
+
+impl Interval {
+    pub const TOP: Interval = Interval {
+        min: Value::MIN,
+        max: Value::MAX,
+    };
+
+    pub fn new(min: Value, max: Value) -> Option<Self> {
+        (min <= max).then_some(Interval { min, max })
+    }
+
+    pub fn constant(value: Value) -> Self {
+        Interval {
+            min: value,
+            max: value,
+        }
+    }
+
+    pub fn as_constant(&self) -> Option<Value> {
+        (self.min == self.max).then_some(self.min)
+    }
+
+    pub fn contains(&self, value: Value) -> bool {
+        (self.min..=self.max).contains(&value)
+    }
+
+    pub fn join(self, other: Self) -> Self {
+        Interval {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    pub fn intersect(self, other: Self) -> Option<Self> {
+        Interval::new(self.min.max(other.min), self.max.min(other.max))
+    }
+
+    /// Whichever bound moved since `self` goes straight to infinity.
+    fn widen(self, newer: Self) -> Self {
+        Interval {
+            min: if newer.min < self.min {
+                Value::MIN
+            } else {
+                self.min
+            },
+            max: if newer.max > self.max {
+                Value::MAX
+            } else {
+                self.max
+            },
+        }
+    }
+
+    // Values that overflow stop the program, so clamping loses nothing. An
+    // unbounded end stays so, whatever it is added to.
+    fn add(self, other: Self) -> Self {
+        Interval {
+            min: match (self.min, other.min) {
+                (Value::MIN, _) | (_, Value::MIN) => Value::MIN,
+                (a, b) => clamp(extended(a) + extended(b)),
+            },
+            max: match (self.max, other.max) {
+                (Value::MAX, _) | (_, Value::MAX) => Value::MAX,
+                (a, b) => clamp(extended(a) + extended(b)),
+            },
+        }
+    }
+
+    fn sub(self, other: Self) -> Self {
+        Interval {
+            min: match (self.min, other.max) {
+                (Value::MIN, _) | (_, Value::MAX) => Value::MIN,
+                (a, b) => clamp(extended(a) - extended(b)),
+            },
+            max: match (self.max, other.min) {
+                (Value::MAX, _) | (_, Value::MIN) => Value::MAX,
+                (a, b) => clamp(extended(a) - extended(b)),
+            },
+        }
+    }
+
+    fn mul(self, other: Self) -> Self {
+        let products = [
+            extended(self.min).saturating_mul(extended(other.min)),
+            extended(self.min).saturating_mul(extended(other.max)),
+            extended(self.max).saturating_mul(extended(other.min)),
+            extended(self.max).saturating_mul(extended(other.max)),
+        ];
+        Interval {
+            min: clamp(products.into_iter().min().unwrap_or_default()),
+            max: clamp(products.into_iter().max().unwrap_or_default()),
+        }
+    }
+
+    /// Truncated remainder. `None` if it always fails.
+    fn rem(self, other: Self) -> Option<Self> {
+        if let (Some(a), Some(b)) = (self.as_constant(), other.as_constant()) {
+            return a.checked_rem(b).map(Interval::constant);
+        }
+        if other.as_constant() == Some(0) {
+            return None;
+        }
+
+        // The result is smaller than the divisor, and has the sign of the
+        // dividend.
+        let largest = i128::from(other.min).abs().max(i128::from(other.max).abs()) - 1;
+        Some(Interval {
+            min: clamp(i128::from(self.min.min(0)).max(-largest)),
+            max: clamp(i128::from(self.max.max(0)).min(largest)),
+        })
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.as_constant() {
+            Some(Value::MIN) => return write!(f, "-inf"),
+            Some(Value::MAX) => return write!(f, "+inf"),
+            Some(value) => return write!(f, "{}", value),
+            None => (),
+        }
+
+        match self.min {
+            Value::MIN => write!(f, "(-inf, ")?,
+            min => write!(f, "[{}, ", min)?,
+        }
+        match self.max {
+            Value::MAX => write!(f, "+inf)"),
+            max => write!(f, "{}]", max),
+        }
+    }
+}
+
+/// The range of every register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct State([Interval; NBR_REGISTERS]);
+
+/// Every register at 0, like a new `Computer`.
+impl Default for State {
+    fn default() -> Self {
+        State([Interval::constant(0); NBR_REGISTERS])
+    }
+}
+
+impl State {
+    /// Every register could hold anything.
+    pub fn top() -> Self {
+        State([Interval::TOP; NBR_REGISTERS])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Register, Interval)> + '_ {
+        Register::all().zip(self.0.iter().copied())
+    }
+
+    fn join(mut self, other: &Self) -> Self {
+        for (interval, other) in self.0.iter_mut().zip(other.0) {
+            *interval = interval.join(other);
+        }
+        self
+    }
+
+    fn widen(mut self, newer: &Self) -> Self {
+        for (interval, newer) in self.0.iter_mut().zip(newer.0) {
+            *interval = interval.widen(newer);
+        }
+        self
+    }
+
+    fn eval(&self, parameter: Parameter) -> Interval {
+        match parameter {
+            Parameter::Value(v) => Interval::constant(v),
+            Parameter::Register(r) => self[r],
+        }
+    }
+}
+
+impl std::ops::Index<Register> for State {
+    type Output = Interval;
+
+    fn index(&self, register: Register) -> &Self::Output {
+        &self.0[register.index()]
+    }
+}
+
+impl std::ops::IndexMut<Register> for State {
+    fn index_mut(&mut self, register: Register) -> &mut Self::Output {
+        &mut self.0[register.index()]
+    }
+}
+
+/// Where an instruction can go, and in what state. `None` is outside of the
+/// program, which halts it.
+type Successors = Vec<(Option<usize>, State)>;
+
+/// What a jump condition may be when the jump is taken, and when it is not.
+/// `None` if that cannot happen.
+fn split(instruction: Instruction, x: Interval) -> (Option<Interval>, Option<Interval>) {
+    if let Some(value) = x.as_constant() {
+        let taken = instruction.is_taken(value) == Some(true);
+        return if taken {
+            (Some(x), None)
+        } else {
+            (None, Some(x))
+        };
+    }
+
+    match instruction {
+        Instruction::JumpGreaterThanZero(_, _) => (
+            x.intersect(Interval {
+                min: 1,
+                max: Value::MAX,
+            }),
+            x.intersect(Interval {
+                min: Value::MIN,
+                max: 0,
+            }),
+        ),
+        Instruction::JumpIfNotZero(_, _) => {
+            let taken = Interval {
+                min: if x.min == 0 { 1 } else { x.min },
+                max: if x.max == 0 { -1 } else { x.max },
+            };
+            (
+                Interval::new(taken.min, taken.max),
+                x.intersect(Interval::constant(0)),
+            )
+        }
+        _ => (Some(x), Some(x)),
+    }
+}
+
+/// Every instruction `offset` away from `pc`, and the outside if it can get
+/// there. Negative targets are errors, and go nowhere.
+fn land(len: usize, pc: usize, offset: Interval, state: State, successors: &mut Successors) {
+    let min = (pc as i128 + i128::from(offset.min)).max(0);
+    let max = pc as i128 + i128::from(offset.max);
+    let last = max.min(len as i128 - 1);
+    if min <= last {
+        successors.extend((min as usize..=last as usize).map(|target| (Some(target), state)));
+    }
+    if max >= len as i128 {
+        successors.push((None, state));
+    }
+}
+
+fn next(len: usize, pc: usize) -> Option<usize> {
+    Some(pc + 1).filter(|&next| next < len)
+}
+
+/// What `instruction`, at `pc` in a program of `len` instructions, does to
+/// `state`.
+fn transfer(len: usize, pc: usize, instruction: Instruction, state: &State) -> Successors {
+    let written = |r: Register, interval: Option<Interval>| match interval {
+        Some(interval) => {
+            let mut state = *state;
+            state[r] = interval;
+            vec![(next(len, pc), state)]
+        }
+        None => Vec::new(),
+    };
+
+    match instruction {
+        Instruction::Send(_) => vec![(next(len, pc), *state)],
+        Instruction::Set(r, p) => written(r, Some(state.eval(p))),
+        Instruction::Add(r, p) => written(r, Some(state[r].add(state.eval(p)))),
+        Instruction::Sub(r, p) => written(r, Some(state[r].sub(state.eval(p)))),
+        Instruction::Multiply(r, p) => written(r, Some(state[r].mul(state.eval(p)))),
+        Instruction::Modulo(r, p) => written(r, state[r].rem(state.eval(p))),
+        // Either receives anything, or only recovers a frequency.
+        Instruction::Receive(r) => written(r, Some(Interval::TOP)),
+        Instruction::Fused(fused) => {
+            let mut successors = transfer(len, pc, fused.first(), state);
+            let mut skipped = *state;
+            for r in fused.writes() {
+                skipped[r] = Interval::TOP;
+            }
+            let target = Some(pc + fused.span()).filter(|&target| target < len);
+            successors.push((target, skipped));
+            successors
+        }
+        Instruction::Extended(opcode, x, y) => match (opcode.behavior, x) {
+            (Behavior::Arithmetic(f) | Behavior::Division(f), Parameter::Register(r)) => {
+                let (a, b) = (state[r], state.eval(y));
+                let divides = matches!(opcode.behavior, Behavior::Division(_));
+                let result = match (a.as_constant(), b.as_constant()) {
+                    (_, Some(0)) if divides => None,
+                    (Some(a), Some(b)) => f(a, b).map(Interval::constant),
+                    _ => Some(Interval::TOP),
+                };
+                written(r, result)
+            }
+            (Behavior::Arithmetic(_) | Behavior::Division(_), Parameter::Value(_)) => Vec::new(),
+            (Behavior::Send, _) => vec![(next(len, pc), *state)],
+            (Behavior::Jump(_), _) => jump(len, pc, instruction, state),
+        },
+        Instruction::JumpGreaterThanZero(_, _) | Instruction::JumpIfNotZero(_, _) => {
+            jump(len, pc, instruction, state)
+        }
+    }
+}
+
+fn jump(len: usize, pc: usize, instruction: Instruction, state: &State) -> Successors {
+    let (x, offset) = match instruction.jump() {
+        Some(operands) => operands,
+        None => return Vec::new(),
+    };
+    let (taken, not_taken) = split(instruction, state.eval(x));
+    let refined = |interval: Interval| {
+        let mut state = *state;
+        if let Parameter::Register(r) = x {
+            state[r] = interval;
+        }
+        state
+    };
+
+    let mut successors = Vec::new();
+    if let Some(taken) = taken {
+        let state = refined(taken);
+        land(len, pc, state.eval(offset), state, &mut successors);
+    }
+    if let Some(not_taken) = not_taken {
+        successors.push((next(len, pc), refined(not_taken)));
+    }
+    successors
+}
+
+/// Runs a program over ranges of values instead of actual values, to find
+/// out what every register may hold before each instruction. This assumes
+/// the default `arithmetic::ArithmeticMode` and `arithmetic::ModuloMode`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalAnalysis {
+    instructions: Vec<Instruction>,
+    /// `None` for instructions that are never executed.
+    states: Vec<Option<State>>,
+}
+
+impl IntervalAnalysis {
+    pub fn new(instructions: &[Instruction], initial: State) -> Self {
+        let len = instructions.len();
+        let mut states: Vec<Option<State>> = vec![None; len];
+        let mut visits = vec![0; len];
+        let mut to_visit = BTreeSet::new();
+        if len > 0 {
+            states[0] = Some(initial);
+            to_visit.insert(0);
+        }
+
+        while let Some(pc) = to_visit.pop_first() {
+            let state = match states[pc] {
+                Some(state) => state,
+                None => continue,
+            };
+            for (target, out) in transfer(len, pc, instructions[pc], &state) {
+                let target = match target {
+                    Some(target) => target,
+                    None => continue,
+                };
+                let new = match states[target] {
+                    None => out,
+                    Some(old) if visits[target] >= WIDEN_AFTER => old.widen(&old.join(&out)),
+                    Some(old) => old.join(&out),
+                };
+                if states[target] != Some(new) {
+                    states[target] = Some(new);
+                    visits[target] += 1;
+                    to_visit.insert(target);
+                }
+            }
+        }
+
+        for _ in 0..NARROWING_PASSES {
+            let mut narrowed: Vec<Option<State>> = vec![None; len];
+            if len > 0 {
+                narrowed[0] = Some(initial);
+            }
+            for (pc, state) in states.iter().enumerate() {
+                let state = match state {
+                    Some(state) => state,
+                    None => continue,
+                };
+                for (target, out) in transfer(len, pc, instructions[pc], state) {
+                    if let Some(target) = target {
+                        narrowed[target] =
+                            Some(narrowed[target].map_or(out, |state| state.join(&out)));
+                    }
+                }
+            }
+            states = narrowed;
+        }
+
+        IntervalAnalysis {
+            instructions: instructions.to_vec(),
+            states,
+        }
+    }
+
+    /// What every register may hold right before the instruction at `pc`,
+    /// or `None` if it is never executed.
+    pub fn state(&self, pc: usize) -> Option<&State> {
+        self.states.get(pc)?.as_ref()
+    }
+
+    pub fn unreachable(&self) -> Vec<usize> {
+        (0..self.states.len())
+            .filter(|&pc| self.states[pc].is_none())
+            .collect()
+    }
+
+    /// Instructions that are executed, but from which the program can never
+    /// run past its end. Once there, it is stuck in an infinite loop, unless
+    /// it stops on an error.
+    pub fn non_terminating(&self) -> BTreeSet<usize> {
+        let len = self.instructions.len();
+        let mut predecessors = vec![Vec::new(); len];
+        let mut to_visit = Vec::new();
+        for (pc, state) in self.states.iter().enumerate() {
+            if let Some(state) = state {
+                for (target, _) in transfer(len, pc, self.instructions[pc], state) {
+                    match target {
+                        Some(target) => predecessors[target].push(pc),
+                        None => to_visit.push(pc),
+                    }
+                }
+            }
+        }
+
+        let mut terminating = BTreeSet::new();
+        while let Some(pc) = to_visit.pop() {
+            if terminating.insert(pc) {
+                to_visit.extend(&predecessors[pc]);
+            }
+        }
+
+        (0..len)
+            .filter(|&pc| self.states[pc].is_some() && !terminating.contains(&pc))
+            .collect()
+    }
+
+    /// The program, with the range of every register at the end of each
+    /// line, leaving out those that are 0 all the way through.
+    pub fn listing(&self) -> String {
+        let non_terminating = self.non_terminating();
+        let zero = State::default();
+        let shown: Vec<bool> = Register::all()
+            .map(|register| {
+                self.states
+                    .iter()
+                    .flatten()
+                    .any(|state| state[register] != zero[register])
+            })
+            .collect();
+        let mut output = String::new();
+        for (pc, instruction) in self.instructions.iter().enumerate() {
+            let _ = write!(output, "{:>4}: {:<20}", pc, instruction.to_string());
+            match &self.states[pc] {
+                None => output.push_str("  unreachable"),
+                Some(state) => {
+                    let ranges: Vec<String> = state
+                        .iter()
+                        .filter(|&(register, _)| shown[register.index()])
+                        .map(|(register, interval)| format!("{} = {}", register, interval))
+                        .collect();
+                    let _ = write!(output, "  {}", ranges.join(", "));
+                    if non_terminating.contains(&pc) {
+                        output.push_str("  (never halts)");
+                    }
+                }
+            }
+            output.push('\n');
+        }
+
+        output
+    }
+}
+
+/// A register changed by the same constant on every iteration of a loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Induction {
+    pub register: Register,
+    pub step: Value,
+    /// Where it is changed.
+    pub pc: usize,
+    /// `false` if only some iterations change it, which makes it a count of
+    /// those iterations.
+    pub every_iteration: bool,
+}
+
+/// A register counting how many of the values an induction variable takes,
+/// from its value when the loop starts up to `end`, are composite numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CompositeCount {
+    pub counter: Register,
+    /// The induction variable.
+    pub candidate: Register,
+    pub step: Value,
+    pub end: Register,
+}
+
+impl Display for CompositeCount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} counts the composite numbers in [{}, {}] step {}",
+            self.counter, self.candidate, self.end, self.step
+        )
+    }
+}
+
+/// What a loop does, as far as can be told without running it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LoopSummary {
+    /// First instruction of the loop.
+    pub header: usize,
+    pub depth: usize,
+    pub inductions: Vec<Induction>,
+    pub composite_counts: Vec<CompositeCount>,
+    /// Superinstructions found directly in the loop, by program counter.
+    pub idioms: Vec<(usize, Instruction)>,
+}
+
+impl Display for LoopSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let indent = "    ".repeat(self.depth - 1);
+        writeln!(f, "{}loop at {}:", indent, self.header)?;
+        for induction in &self.inductions {
+            let (operator, step) = if induction.step < 0 {
+                ("-=", induction.step.unsigned_abs())
+            } else {
+                ("+=", induction.step.unsigned_abs())
+            };
+            let when = if induction.every_iteration {
+                "every iteration"
+            } else {
+                "on some iterations"
+            };
+            writeln!(
+                f,
+                "{}    {} {} {} {} (at {})",
+                indent, induction.register, operator, step, when, induction.pc
+            )?;
+        }
+        for count in &self.composite_counts {
+            writeln!(f, "{}    {}", indent, count)?;
+        }
+        for (pc, instruction) in &self.idioms {
+            writeln!(f, "{}    {} (at {})", indent, instruction, pc)?;
+        }
+        Ok(())
+    }
+}
+
+/// `add r K` or `sub r K`, as `(r, step)`.
+fn constant_step(instruction: Instruction) -> Option<(Register, Value)> {
+    match instruction {
+        Instruction::Add(r, Parameter::Value(v)) => Some((r, v)),
+        Instruction::Sub(r, Parameter::Value(v)) => Some((r, v.checked_neg()?)),
+        _ => None,
+    }
+}
+
+/// Recognizes, in the loop made of `pcs`, the count of day 23:
+/// ```text
+///     set F 1
+///     set D 2
+///     (CompositeCheck of N, from 2, clearing F)
+///     ...
+///     jnz F 2
+///     sub H -1
+///     set G N
+///     sub G C
+///     jnz G 2
+/// ```
+/// where `H` is incremented on some iterations, and `N` on all of them.
+fn composite_count(
+    instructions: &[Instruction],
+    idioms: &[Instruction],
+    pcs: &[usize],
+    inductions: &[Induction],
+) -> Vec<CompositeCount> {
+    let at = |pc: usize| instructions.get(pc).copied();
+    // The flag must be set again for every candidate.
+    let resets = |flag: Register| {
+        pcs.iter().any(|&pc| {
+            matches!(at(pc), Some(Instruction::Set(r, Parameter::Value(v))) if r == flag && v != 0)
+        })
+    };
+    // `N` must be compared with the end, to leave the loop.
+    let end = |n: Register| {
+        pcs.iter()
+            .find_map(|&pc| match (at(pc)?, at(pc + 1)?, at(pc + 2)?) {
+                (
+                    Instruction::Set(g, Parameter::Register(r)),
+                    Instruction::Sub(h, Parameter::Register(c)),
+                    Instruction::JumpIfNotZero(Parameter::Register(i), _),
+                ) if r == n && g == h && g == i => Some(c),
+                _ => None,
+            })
+    };
+
+    inductions
+        .iter()
+        .filter(|count| !count.every_iteration && count.step == 1)
+        .filter_map(|count| {
+            let flag = match at(count.pc.checked_sub(1)?)? {
+                Instruction::JumpIfNotZero(Parameter::Register(f), Parameter::Value(2)) => f,
+                _ => return None,
+            };
+            let (candidate, step) = pcs.iter().find_map(|&pc| match idioms[pc] {
+                Instruction::Fused(Fused::CompositeCheck {
+                    divisor,
+                    start: Parameter::Value(2),
+                    target,
+                    flag: f,
+                    ..
+                }) if f == flag
+                    && pc > 0
+                    && at(pc - 1) == Some(Instruction::Set(divisor, Parameter::Value(2))) =>
+                {
+                    let candidate = inductions
+                        .iter()
+                        .find(|i| i.register == target && i.every_iteration)?;
+                    Some((target, candidate.step))
+                }
+                _ => None,
+            })?;
+            if !resets(flag) {
+                return None;
+            }
+            Some(CompositeCount {
+                counter: count.register,
+                candidate,
+                step,
+                end: end(candidate)?,
+            })
+        })
+        .collect()
+}
+
+/// Every loop, in program order, with its induction variables: registers
+/// that only change by a constant step, once per iteration at most.
+pub fn summarize_loops(instructions: &[Instruction]) -> Vec<LoopSummary> {
+    let cfg = ControlFlowGraph::new(instructions);
+    // Idioms are only recognized in the original code, and a superinstruction
+    // replaces only the first instruction of its idiom, so both line up.
+    let idioms: Vec<Instruction> = optimize(instructions)
+        .into_iter()
+        .zip(instructions)
+        .map(|(optimized, &instruction)| match instruction {
+            Instruction::Fused(_) => instruction,
+            _ => optimized,
+        })
+        .collect();
+    let dominators = cfg.dominators();
+    let loops = cfg.loops();
+
+    let mut summaries: Vec<LoopSummary> = loops
+        .iter()
+        .map(|l| {
+            // Instructions in nested loops run several times per iteration.
+            let nested: BTreeSet<usize> = loops
+                .iter()
+                .filter(|inner| inner.header != l.header && l.blocks.is_superset(&inner.blocks))
+                .flat_map(|inner| inner.blocks.iter().copied())
+                .collect();
+            let pcs = |blocks: &BTreeSet<usize>| -> Vec<usize> {
+                blocks
+                    .iter()
+                    .flat_map(|&block| cfg.blocks[block].start..cfg.blocks[block].end)
+                    .collect()
+            };
+
+            let mut writes: Vec<(usize, Register)> = Vec::new();
+            for pc in pcs(&l.blocks) {
+                match instructions[pc] {
+                    Instruction::Fused(fused) => {
+                        writes.extend(fused.writes().into_iter().map(|r| (pc, r)))
+                    }
+                    instruction => writes.extend(instruction.destination().map(|r| (pc, r))),
+                }
+            }
+
+            let inductions: Vec<Induction> = Register::all()
+                .filter_map(|register| {
+                    let mut pcs = writes.iter().filter(|&&(_, r)| r == register);
+                    let (pc, _) = *pcs.next()?;
+                    if pcs.next().is_some() {
+                        return None;
+                    }
+                    let block = cfg.block_of(pc)?;
+                    if nested.contains(&block) {
+                        return None;
+                    }
+                    let (_, step) = constant_step(instructions[pc])?;
+                    let every_iteration = l
+                        .latches
+                        .iter()
+                        .all(|&latch| dominators[latch].contains(&block));
+                    Some(Induction {
+                        register,
+                        step,
+                        pc,
+                        every_iteration,
+                    })
+                })
+                .collect();
+
+            let composite_counts =
+                composite_count(instructions, &idioms, &pcs(&l.blocks), &inductions);
+
+            let direct: BTreeSet<usize> = l.blocks.difference(&nested).copied().collect();
+            let idioms = pcs(&direct)
+                .into_iter()
+                .filter(|&pc| matches!(instructions[pc], Instruction::Fused(_)))
+                .map(|pc| (pc, instructions[pc]))
+                .collect();
+
+            LoopSummary {
+                header: cfg.blocks[l.header].start,
+                depth: l.depth,
+                inductions,
+                composite_counts,
+                idioms,
+            }
+        })
+        .collect();
+
+    // Nested loops right after the loop they are in.
+    summaries.sort_by_key(|summary| (summary.header, summary.depth));
+    summaries
+}
@@ -0,0 +1,209 @@
+use super::Value;
+use std::collections::VecDeque;
+use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IoError {
+    /// The other end is gone; nothing will ever be sent or received again.
+    Disconnected,
+    /// This kind of I/O does not support the operation at all.
+    Unsupported,
+}
+
+impl std::fmt::Display for IoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IoError::Disconnected => write!(f, "I/O endpoint is disconnected"),
+            IoError::Unsupported => write!(f, "I/O is not supported"),
+        }
+    }
+}
+
+impl std::error::Error for IoError {}
+
+/// Where a `Computer` sends its values to, and receives them from.
+pub trait Io {
+    fn send(&mut self, value: Value) -> Result<(), IoError>;
+
+    /// Wait for a value.
+    fn receive(&mut self) -> Result<Value, IoError>;
+
+    /// Get a value if there is one available right now, without waiting.
+    fn try_receive(&mut self) -> Result<Option<Value>, IoError>;
+
+    /// Every value available right now, in the order they would be received,
+    /// without consuming them.
+    fn pending(&mut self) -> Result<Vec<Value>, IoError> {
+        Err(IoError::Unsupported)
+    }
+
+    /// Replace the values waiting to be received.
+    fn set_pending(&mut self, _values: &[Value]) -> Result<(), IoError> {
+        Err(IoError::Unsupported)
+    }
+}
+
+#[derive(Debug)]
+pub struct Channel {
+    sender: Sender<Value>,
+    receiver: Receiver<Value>,
+    /// Values taken out of the receiver, but not received yet.
+    buffer: VecDeque<Value>,
+}
+
+impl Channel {
+    pub fn new(sender: Sender<Value>, receiver: Receiver<Value>) -> Self {
+        Channel {
+            sender,
+            receiver,
+            buffer: VecDeque::new(),
+        }
+    }
+}
+
+impl Io for Channel {
+    fn send(&mut self, value: Value) -> Result<(), IoError> {
+        // Nobody listening is not our problem, the value is simply lost.
+        let _ = self.sender.send(value);
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<Value, IoError> {
+        match self.buffer.pop_front() {
+            Some(value) => Ok(value),
+            None => self.receiver.recv().map_err(|_| IoError::Disconnected),
+        }
+    }
+
+    fn try_receive(&mut self) -> Result<Option<Value>, IoError> {
+        if let Some(value) = self.buffer.pop_front() {
+            return Ok(Some(value));
+        }
+
+        match self.receiver.try_recv() {
+            Ok(value) => Ok(Some(value)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(IoError::Disconnected),
+        }
+    }
+
+    fn pending(&mut self) -> Result<Vec<Value>, IoError> {
+        self.buffer.extend(self.receiver.try_iter());
+        Ok(self.buffer.iter().copied().collect())
+    }
+
+    fn set_pending(&mut self, values: &[Value]) -> Result<(), IoError> {
+        // Whatever is still in flight is replaced too.
+        self.receiver.try_iter().for_each(drop);
+        self.buffer = values.iter().copied().collect();
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Queue {
+    pub input: VecDeque<Value>,
+    pub output: VecDeque<Value>,
+}
+
+impl Queue {
+    pub fn new(input: impl IntoIterator<Item = Value>) -> Self {
+        Queue {
+            input: input.into_iter().collect(),
+            output: VecDeque::new(),
+        }
+    }
+}
+
+impl Io for Queue {
+    fn send(&mut self, value: Value) -> Result<(), IoError> {
+        self.output.push_back(value);
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<Value, IoError> {
+        // Waiting would be pointless, since nobody else can fill the queue
+        // while we are.
+        self.input.pop_front().ok_or(IoError::Disconnected)
+    }
+
+    fn try_receive(&mut self) -> Result<Option<Value>, IoError> {
+        Ok(self.input.pop_front())
+    }
+
+    fn pending(&mut self) -> Result<Vec<Value>, IoError> {
+        Ok(self.input.iter().copied().collect())
+    }
+
+    fn set_pending(&mut self, values: &[Value]) -> Result<(), IoError> {
+        self.input = values.iter().copied().collect();
+        Ok(())
+    }
+}
+
+/// Delegates to a pair of closures. The receiving closure returns `None` when
+/// there is nothing to receive.
+#[derive(Debug, Clone, Copy)]
+pub struct Closures<S, R> {
+    send: S,
+    receive: R,
+}
+
+impl<S, R> Closures<S, R>
+where
+    S: FnMut(Value),
+    R: FnMut() -> Option<Value>,
+{
+    pub fn new(send: S, receive: R) -> Self {
+        Closures { send, receive }
+    }
+}
+
+impl<S, R> Io for Closures<S, R>
+where
+    S: FnMut(Value),
+    R: FnMut() -> Option<Value>,
+{
+    fn send(&mut self, value: Value) -> Result<(), IoError> {
+        (self.send)(value);
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<Value, IoError> {
+        (self.receive)().ok_or(IoError::Disconnected)
+    }
+
+    fn try_receive(&mut self) -> Result<Option<Value>, IoError> {
+        Ok((self.receive)())
+    }
+}
+
+/// For programs that are not supposed to communicate at all.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NoIo;
+
+impl Io for NoIo {
+    fn send(&mut self, _value: Value) -> Result<(), IoError> {
+        Err(IoError::Unsupported)
+    }
+
+    fn receive(&mut self) -> Result<Value, IoError> {
+        Err(IoError::Unsupported)
+    }
+
+    fn try_receive(&mut self) -> Result<Option<Value>, IoError> {
+        Err(IoError::Unsupported)
+    }
+
+    fn pending(&mut self) -> Result<Vec<Value>, IoError> {
+        Ok(Vec::new())
+    }
+
+    fn set_pending(&mut self, values: &[Value]) -> Result<(), IoError> {
+        if values.is_empty() {
+            Ok(())
+        } else {
+            Err(IoError::Unsupported)
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use super::io::Queue;
+use super::scheduler::{round_robin, Report};
+use super::{Computer, Instruction, Register, Value};
+use std::collections::BTreeMap;
+
+/// Who receives what each machine sends. A value sent to several machines is
+/// copied to every one of them, and a value sent to none stays in the
+/// output of its sender.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Topology {
+    successors: Vec<Vec<usize>>,
+}
+
+impl Topology {
+    /// Every machine sends to the next one, and the last one to the first.
+    pub fn ring(machines: usize) -> Self {
+        Topology {
+            successors: (0..machines)
+                .map(|idx| vec![(idx + 1) % machines])
+                .collect(),
+        }
+    }
+
+    /// Every machine sends to the next one, and the last one keeps its output.
+    pub fn pipeline(machines: usize) -> Self {
+        Topology {
+            successors: (0..machines)
+                .map(|idx| (idx + 1..machines).take(1).collect())
+                .collect(),
+        }
+    }
+
+    /// Every machine sends to every other one.
+    pub fn broadcast(machines: usize) -> Self {
+        Topology {
+            successors: (0..machines)
+                .map(|idx| (0..machines).filter(|&other| other != idx).collect())
+                .collect(),
+        }
+    }
+
+    /// Machines missing from `map` send to nobody.
+    pub fn adjacency(machines: usize, map: &BTreeMap<usize, Vec<usize>>) -> Result<Self, String> {
+        let mut successors = vec![Vec::new(); machines];
+        for (&from, to) in map {
+            if let Some(&idx) = std::iter::once(&from)
+                .chain(to)
+                .find(|&&idx| idx >= machines)
+            {
+                return Err(format!("No such machine: {}", idx));
+            }
+            successors[from] = to.clone();
+        }
+
+        Ok(Topology { successors })
+    }
+
+    pub fn len(&self) -> usize {
+        self.successors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.successors.is_empty()
+    }
+
+    pub fn successors(&self, machine: usize) -> &[usize] {
+        &self.successors[machine]
+    }
+}
+
+/// A value delivered from one machine to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Message {
+    pub from: usize,
+    pub to: usize,
+    pub value: Value,
+}
+
+/// A group of `Computer`s wired together by a `Topology`, and run
+/// cooperatively on the current thread, like a `scheduler::Scheduler`.
+#[derive(Debug)]
+pub struct Network {
+    computers: Vec<Computer<Queue>>,
+    topology: Topology,
+    /// Messages delivered over each link, by `(from, to)`.
+    counters: BTreeMap<(usize, usize), usize>,
+    trace: Option<Vec<Message>>,
+}
+
+impl Network {
+    pub fn new(computers: Vec<Computer<Queue>>, topology: Topology) -> Result<Self, String> {
+        if computers.len() != topology.len() {
+            return Err(format!(
+                "{} machines for a topology of {}",
+                computers.len(),
+                topology.len()
+            ));
+        }
+
+        Ok(Network {
+            computers,
+            topology,
+            counters: BTreeMap::new(),
+            trace: None,
+        })
+    }
+
+    /// Every machine runs the same program, with its index in register `p`,
+    /// as in day 18.
+    pub fn with_program(instructions: &[Instruction], topology: Topology) -> Self {
+        let p = Register::new('p').expect("`p` is a valid register.");
+        let computers = (0..topology.len())
+            .map(|idx| {
+                let mut computer = Computer::with_io(instructions.to_vec(), Queue::default());
+                computer.set_register(p, idx as Value);
+                computer
+            })
+            .collect();
+
+        Network {
+            computers,
+            topology,
+            counters: BTreeMap::new(),
+            trace: None,
+        }
+    }
+
+    pub fn computers(&self) -> &[Computer<Queue>] {
+        &self.computers
+    }
+
+    pub fn computers_mut(&mut self) -> &mut [Computer<Queue>] {
+        &mut self.computers
+    }
+
+    pub fn into_computers(self) -> Vec<Computer<Queue>> {
+        self.computers
+    }
+
+    pub fn topology(&self) -> &Topology {
+        &self.topology
+    }
+
+    /// How many messages went from `from` to `to`.
+    pub fn messages(&self, from: usize, to: usize) -> usize {
+        self.counters.get(&(from, to)).copied().unwrap_or_default()
+    }
+
+    /// Every link that carried at least one message, with its count.
+    pub fn links(&self) -> impl Iterator<Item = (usize, usize, usize)> + '_ {
+        self.counters
+            .iter()
+            .map(|(&(from, to), &count)| (from, to, count))
+    }
+
+    /// Start recording every message delivered, in order.
+    pub fn enable_tracing(&mut self) {
+        self.trace.get_or_insert_with(Vec::new);
+    }
+
+    /// Everything recorded since tracing was enabled.
+    pub fn trace(&self) -> &[Message] {
+        self.trace.as_deref().unwrap_or_default()
+    }
+
+    /// Run until the network is quiet: every machine has halted, failed or is
+    /// waiting for a message that nobody is left to send.
+    pub fn run(&mut self) -> Report {
+        let Network {
+            computers,
+            topology,
+            counters,
+            trace,
+        } = self;
+        round_robin(computers, |computers, from| {
+            // Move what `from` sent to its successors.
+            if topology.successors(from).is_empty() {
+                return;
+            }
+            let values: Vec<Value> = computers[from].io_mut().output.drain(..).collect();
+            for value in values {
+                for &to in topology.successors(from) {
+                    computers[to].io_mut().input.push_back(value);
+                    *counters.entry((from, to)).or_default() += 1;
+                    if let Some(trace) = trace.as_mut() {
+                        trace.push(Message { from, to, value });
+                    }
+                }
+            }
+        })
+    }
+}
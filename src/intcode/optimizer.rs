@@ -0,0 +1,414 @@
+use super::io::Io;
+use super::{Computer, ExecError, Instruction, Parameter, Register, Value};
+
+/// A whole idiom, executed as a single instruction.
+///
+/// A superinstruction replaces only the first instruction of its idiom; the
+/// others are left in place, so jump offsets stay valid and jumping into the
+/// middle of the idiom still works. When it cannot prove that it would do
+/// exactly what the original instructions do (on overflow, or when a loop
+/// would not terminate, for example), it executes that first instruction
+/// instead, and the rest of the original code carries on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fused {
+    /// ```text
+    /// add A B
+    /// sub C 1
+    /// jnz C -2
+    /// ```
+    MultiplyAdd {
+        accumulator: Register,
+        addend: Parameter,
+        counter: Register,
+    },
+    /// Clears `flag` if `divisor * counter == target` for any `counter` from
+    /// its current value up to `target`.
+    /// ```text
+    /// set T D
+    /// mul T E
+    /// sub T N
+    /// jnz T 2
+    /// set F 0
+    /// sub E -1
+    /// set T E
+    /// sub T N
+    /// jnz T -8
+    /// ```
+    DivisorSearch {
+        temp: Register,
+        divisor: Register,
+        counter: Register,
+        target: Register,
+        flag: Register,
+    },
+    /// A `DivisorSearch` for every `divisor` from its current value up to
+    /// `target`, so `flag` is cleared if `target` is composite.
+    /// ```text
+    /// set E S
+    /// (DivisorSearch)
+    /// sub D -1
+    /// set T D
+    /// sub T N
+    /// jnz T -13
+    /// ```
+    CompositeCheck {
+        temp: Register,
+        divisor: Register,
+        counter: Register,
+        start: Parameter,
+        target: Register,
+        flag: Register,
+    },
+}
+
+impl Fused {
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Fused::MultiplyAdd { .. } => "mac",
+            Fused::DivisorSearch { .. } => "divisors",
+            Fused::CompositeCheck { .. } => "composite",
+        }
+    }
+
+    /// How many of the original instructions this replaces.
+    pub fn span(&self) -> usize {
+        match self {
+            Fused::MultiplyAdd { .. } => 3,
+            Fused::DivisorSearch { .. } => 9,
+            Fused::CompositeCheck { .. } => 14,
+        }
+    }
+
+    /// Every register the whole idiom may write to.
+    pub fn writes(&self) -> Vec<Register> {
+        match *self {
+            Fused::MultiplyAdd {
+                accumulator,
+                counter,
+                ..
+            } => vec![accumulator, counter],
+            Fused::DivisorSearch {
+                temp,
+                counter,
+                flag,
+                ..
+            } => vec![temp, counter, flag],
+            Fused::CompositeCheck {
+                temp,
+                divisor,
+                counter,
+                flag,
+                ..
+            } => vec![temp, divisor, counter, flag],
+        }
+    }
+
+    /// The original instruction this replaced.
+    pub fn first(&self) -> Instruction {
+        match *self {
+            Fused::MultiplyAdd {
+                accumulator,
+                addend,
+                ..
+            } => Instruction::Add(accumulator, addend),
+            Fused::DivisorSearch { temp, divisor, .. } => {
+                Instruction::Set(temp, Parameter::Register(divisor))
+            }
+            Fused::CompositeCheck { counter, start, .. } => Instruction::Set(counter, start),
+        }
+    }
+}
+
+/// The mnemonic, followed by every operand in the order they are declared.
+impl std::fmt::Display for Fused {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mnemonic = self.mnemonic();
+        match self {
+            Fused::MultiplyAdd {
+                accumulator,
+                addend,
+                counter,
+            } => write!(f, "{} {} {} {}", mnemonic, accumulator, addend, counter),
+            Fused::DivisorSearch {
+                temp,
+                divisor,
+                counter,
+                target,
+                flag,
+            } => write!(
+                f,
+                "{} {} {} {} {} {}",
+                mnemonic, temp, divisor, counter, target, flag
+            ),
+            Fused::CompositeCheck {
+                temp,
+                divisor,
+                counter,
+                start,
+                target,
+                flag,
+            } => write!(
+                f,
+                "{} {} {} {} {} {} {}",
+                mnemonic, temp, divisor, counter, start, target, flag
+            ),
+        }
+    }
+}
+
+/// `sub r -1` or `add r 1`.
+fn is_increment(instruction: Instruction, register: Register) -> bool {
+    matches!(
+        instruction,
+        Instruction::Sub(r, Parameter::Value(-1)) | Instruction::Add(r, Parameter::Value(1))
+            if r == register
+    )
+}
+
+/// `sub r 1` or `add r -1`.
+fn is_decrement(instruction: Instruction, register: Register) -> bool {
+    matches!(
+        instruction,
+        Instruction::Sub(r, Parameter::Value(1)) | Instruction::Add(r, Parameter::Value(-1))
+            if r == register
+    )
+}
+
+fn is_loop(instruction: Instruction, register: Register, offset: Value) -> bool {
+    instruction
+        == Instruction::JumpIfNotZero(Parameter::Register(register), Parameter::Value(offset))
+}
+
+fn distinct(registers: &[Register]) -> bool {
+    registers
+        .iter()
+        .enumerate()
+        .all(|(idx, r)| !registers[idx + 1..].contains(r))
+}
+
+fn multiply_add(code: &[Instruction]) -> Option<Fused> {
+    let (accumulator, addend, counter) = match code {
+        [Instruction::Add(a, b), decrement, Instruction::JumpIfNotZero(Parameter::Register(c), Parameter::Value(-2)), ..]
+            if is_decrement(*decrement, *c) =>
+        {
+            (*a, *b, *c)
+        }
+        _ => return None,
+    };
+
+    let addend_register = match addend {
+        Parameter::Register(r) => Some(r),
+        Parameter::Value(_) => None,
+    };
+    let mut registers = vec![accumulator, counter];
+    registers.extend(addend_register);
+
+    distinct(&registers).then_some(Fused::MultiplyAdd {
+        accumulator,
+        addend,
+        counter,
+    })
+}
+
+fn divisor_search(code: &[Instruction]) -> Option<Fused> {
+    let (temp, divisor, counter, target, flag) = match *code {
+        [Instruction::Set(t, Parameter::Register(d)), Instruction::Multiply(t2, Parameter::Register(e)), Instruction::Sub(t3, Parameter::Register(n)), Instruction::JumpIfNotZero(Parameter::Register(t4), Parameter::Value(2)), Instruction::Set(f, Parameter::Value(0)), increment, Instruction::Set(t5, Parameter::Register(e2)), Instruction::Sub(t6, Parameter::Register(n2)), jump, ..]
+            if [t2, t3, t4, t5, t6].iter().all(|&r| r == t)
+                && e2 == e
+                && n2 == n
+                && is_increment(increment, e)
+                && is_loop(jump, t, -8) =>
+        {
+            (t, d, e, n, f)
+        }
+        _ => return None,
+    };
+
+    distinct(&[temp, divisor, counter, target, flag]).then_some(Fused::DivisorSearch {
+        temp,
+        divisor,
+        counter,
+        target,
+        flag,
+    })
+}
+
+fn composite_check(code: &[Instruction]) -> Option<Fused> {
+    let (counter, start) = match code.first()? {
+        Instruction::Set(e, start) => (*e, *start),
+        _ => return None,
+    };
+    let (temp, divisor, target, flag) = match divisor_search(code.get(1..)?)? {
+        Fused::DivisorSearch {
+            temp,
+            divisor,
+            counter: e,
+            target,
+            flag,
+        } if e == counter => (temp, divisor, target, flag),
+        _ => return None,
+    };
+
+    match *code.get(10..14)? {
+        [increment, Instruction::Set(t, Parameter::Register(d)), Instruction::Sub(t2, Parameter::Register(n)), jump]
+            if is_increment(increment, divisor)
+                && t == temp
+                && t2 == temp
+                && d == divisor
+                && n == target
+                && is_loop(jump, temp, -13) => {}
+        _ => return None,
+    }
+
+    // The start value must not be changed by the loop itself.
+    if let Parameter::Register(r) = start {
+        if !distinct(&[r, counter, temp, divisor, target, flag]) {
+            return None;
+        }
+    }
+
+    Some(Fused::CompositeCheck {
+        temp,
+        divisor,
+        counter,
+        start,
+        target,
+        flag,
+    })
+}
+
+/// Replace every idiom that is recognized by a superinstruction. The result
+/// has exactly as many instructions as the original.
+pub fn optimize(instructions: &[Instruction]) -> Vec<Instruction> {
+    let mut optimized = instructions.to_vec();
+
+    for (pc, instruction) in optimized.iter_mut().enumerate() {
+        let code = &instructions[pc..];
+        if let Some(fused) = composite_check(code)
+            .or_else(|| divisor_search(code))
+            .or_else(|| multiply_add(code))
+        {
+            *instruction = Instruction::Fused(fused);
+        }
+    }
+
+    optimized
+}
+
+/// Whether `value` is in `start..end`.
+fn within(value: Value, start: Value, end: Value) -> bool {
+    (start..end).contains(&value)
+}
+
+impl<T: Io> Computer<T> {
+    /// Execute a superinstruction, or return `None` if the original
+    /// instructions should be executed instead.
+    fn try_fused(&mut self, fused: Fused) -> Option<()> {
+        // Superinstructions only know about plain values.
+        if self.has_big_values() {
+            return None;
+        }
+
+        match fused {
+            Fused::MultiplyAdd {
+                accumulator,
+                addend,
+                counter,
+            } => {
+                let count = self.register(counter);
+                if count <= 0 {
+                    return None;
+                }
+                let result = self
+                    .get_value(addend)
+                    .checked_mul(count)?
+                    .checked_add(self.register(accumulator))?;
+
+                self.set_register(accumulator, result);
+                self.set_register(counter, 0);
+            }
+            Fused::DivisorSearch {
+                temp,
+                divisor,
+                counter,
+                target,
+                flag,
+            } => {
+                let (d, e, n) = (
+                    self.register(divisor),
+                    self.register(counter),
+                    self.register(target),
+                );
+                if e >= n {
+                    return None;
+                }
+                // Every intermediate value is linear in the counter, so it is
+                // enough to check both ends.
+                for e in [e, n - 1] {
+                    d.checked_mul(e)?.checked_sub(n)?;
+                    e.checked_add(1)?.checked_sub(n)?;
+                }
+
+                let found = if d == 0 {
+                    n == 0
+                } else {
+                    n.checked_rem(d)? == 0 && within(n.checked_div(d)?, e, n)
+                };
+                if found {
+                    self.set_register(flag, 0);
+                }
+                self.set_register(temp, 0);
+                self.set_register(counter, n);
+            }
+            Fused::CompositeCheck {
+                temp,
+                divisor,
+                counter,
+                start,
+                target,
+                flag,
+            } => {
+                let (s, d, n) = (
+                    self.get_value(start),
+                    self.register(divisor),
+                    self.register(target),
+                );
+                // Keep to the (positive) cases where a divisor can only be
+                // found below the square root.
+                if s < 1 || d < 1 || s >= n || d >= n {
+                    return None;
+                }
+                // Products are bilinear, so the corners are the extremes.
+                for d in [d, n - 1] {
+                    for e in [s, n - 1] {
+                        d.checked_mul(e)?.checked_sub(n)?;
+                    }
+                }
+
+                let found = (1..)
+                    .take_while(|&p: &Value| p.checked_mul(p).is_some_and(|square| square <= n))
+                    .filter(|&p| n % p == 0)
+                    .any(|p| {
+                        let q = n / p;
+                        (within(p, d, n) && within(q, s, n)) || (within(q, d, n) && within(p, s, n))
+                    });
+                if found {
+                    self.set_register(flag, 0);
+                }
+                self.set_register(temp, 0);
+                self.set_register(counter, n);
+                self.set_register(divisor, n);
+            }
+        }
+
+        self.instruction_pointer += fused.span();
+        Some(())
+    }
+
+    pub(super) fn fused(&mut self, fused: Fused) -> Result<(), ExecError> {
+        match self.try_fused(fused) {
+            Some(()) => Ok(()),
+            None => self.execute(fused.first()),
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use super::budget::Outcome;
+use super::io::Io;
+use super::{Computer, ExecError, Instruction};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Branch {
+    pub taken: usize,
+    pub not_taken: usize,
+}
+
+/// Everything that was executed during a single run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub steps: usize,
+    /// How many times each instruction was executed, indexed by program counter.
+    pub counts: Vec<usize>,
+    pub opcodes: BTreeMap<&'static str, usize>,
+    /// Outcomes of every jump instruction, indexed by program counter.
+    pub branches: BTreeMap<usize, Branch>,
+    /// `None` if the program simply ran past its last instruction.
+    pub error: Option<ExecError>,
+}
+
+impl Profile {
+    fn new(instructions: &[Instruction]) -> Self {
+        Profile {
+            steps: 0,
+            counts: vec![0; instructions.len()],
+            opcodes: BTreeMap::new(),
+            branches: instructions
+                .iter()
+                .enumerate()
+                .filter(|(_, instruction)| instruction.is_jump())
+                .map(|(pc, _)| (pc, Branch::default()))
+                .collect(),
+            error: None,
+        }
+    }
+
+    pub fn opcode(&self, mnemonic: &str) -> usize {
+        self.opcodes.get(mnemonic).copied().unwrap_or_default()
+    }
+
+    /// The program, with execution counts in the margin and branch outcomes
+    /// at the end of jump lines.
+    pub fn listing(&self, instructions: &[Instruction]) -> String {
+        let mut output = String::new();
+        for (pc, instruction) in instructions.iter().enumerate() {
+            let count = self.counts.get(pc).copied().unwrap_or_default();
+            let _ = write!(output, "{:>12} {:>4}: {}", count, pc, instruction);
+            if let Some(branch) = self.branches.get(&pc) {
+                let _ = write!(
+                    output,
+                    "  [taken: {}, not taken: {}]",
+                    branch.taken, branch.not_taken
+                );
+            }
+            output.push('\n');
+        }
+
+        output
+    }
+}
+
+impl<T: Io> Computer<T> {
+    /// Whether the current instruction is a jump that is about to be taken.
+    fn branch_taken(&self) -> Option<bool> {
+        let instruction = self.current_instruction()?;
+        let (x, _) = instruction.jump()?;
+        instruction.is_taken(self.get_value(x))
+    }
+
+    /// Run the program to the end, keeping track of what was executed.
+    pub fn profile(&mut self) -> Profile {
+        self.profile_until(None)
+    }
+
+    /// Like `profile`, but with a budget of at most `max_steps` steps, as for
+    /// `run_with_budget`. When it runs out, the profile still has everything
+    /// executed until then.
+    pub fn profile_with_budget(&mut self, max_steps: usize) -> (Profile, Outcome) {
+        let profile = self.profile_until(Some(max_steps));
+        let outcome = Outcome::new(profile.error, profile.steps);
+        (profile, outcome)
+    }
+
+    fn profile_until(&mut self, step_limit: Option<usize>) -> Profile {
+        let mut profile = Profile::new(&self.instructions);
+
+        while let Some(instruction) = self.current_instruction() {
+            if step_limit.is_some_and(|limit| profile.steps >= limit) {
+                profile.error = Some(ExecError::StepLimitExhausted);
+                break;
+            }
+            let pc = self.instruction_pointer;
+            let taken = self.branch_taken();
+
+            if let Err(error) = self.step() {
+                profile.error = Some(error);
+                break;
+            }
+
+            profile.steps += 1;
+            profile.counts[pc] += 1;
+            *profile.opcodes.entry(instruction.mnemonic()).or_default() += 1;
+            if let (Some(taken), Some(branch)) = (taken, profile.branches.get_mut(&pc)) {
+                if taken {
+                    branch.taken += 1;
+                } else {
+                    branch.not_taken += 1;
+                }
+            }
+        }
+
+        profile
+    }
+}
@@ -0,0 +1,93 @@
+use super::{ParseError, Value};
+use std::str::FromStr;
+
+pub(super) const NBR_REGISTERS: usize = 26;
+
+/// One of the registers `a` to `z`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Register(u8);
+
+impl Register {
+    pub const fn new(name: char) -> Option<Self> {
+        if name.is_ascii_lowercase() {
+            Some(Register(name as u8 - b'a'))
+        } else {
+            None
+        }
+    }
+
+    pub fn name(self) -> char {
+        char::from(b'a' + self.0)
+    }
+
+    pub fn index(self) -> usize {
+        usize::from(self.0)
+    }
+
+    pub fn all() -> impl Iterator<Item = Register> {
+        (0..NBR_REGISTERS as u8).map(Register)
+    }
+}
+
+impl std::fmt::Debug for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.name())
+    }
+}
+
+impl std::fmt::Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Register {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(name), None) => Register::new(name),
+            _ => None,
+        }
+        .ok_or_else(|| ParseError::InvalidRegister(s.to_string()))
+    }
+}
+
+impl TryFrom<char> for Register {
+    type Error = ParseError;
+
+    fn try_from(name: char) -> Result<Self, Self::Error> {
+        Register::new(name).ok_or_else(|| ParseError::InvalidRegister(name.to_string()))
+    }
+}
+
+/// Every register, all starting at 0.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Registers([Value; NBR_REGISTERS]);
+
+impl Registers {
+    /// Every register with its value, in alphabetical order.
+    pub fn iter(&self) -> impl Iterator<Item = (Register, Value)> + '_ {
+        Register::all().zip(self.0.iter().copied())
+    }
+
+    /// Only the registers that are not 0.
+    pub fn non_zero(&self) -> impl Iterator<Item = (Register, Value)> + '_ {
+        self.iter().filter(|&(_, value)| value != 0)
+    }
+}
+
+impl std::ops::Index<Register> for Registers {
+    type Output = Value;
+
+    fn index(&self, register: Register) -> &Self::Output {
+        &self.0[register.index()]
+    }
+}
+
+impl std::ops::IndexMut<Register> for Registers {
+    fn index_mut(&mut self, register: Register) -> &mut Self::Output {
+        &mut self.0[register.index()]
+    }
+}
@@ -0,0 +1,117 @@
+use super::io::{Channel, Io};
+use super::{Computer, ExecError, Status};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Termination {
+    /// Every machine ran off the end of its program.
+    Halted,
+    /// Every machine is waiting on an empty queue.
+    Deadlock,
+    /// Some machines are waiting on an empty queue, and all the others have
+    /// halted or failed, so nobody is left to fill it.
+    Starved,
+    /// Nobody is waiting, but at least one machine stopped on an error.
+    Error,
+}
+
+impl Termination {
+    pub(super) fn new(blocked: &[bool], errors: &[Option<ExecError>]) -> Self {
+        if !blocked.is_empty() && blocked.iter().all(|&blocked| blocked) {
+            Termination::Deadlock
+        } else if blocked.contains(&true) {
+            Termination::Starved
+        } else if errors.iter().any(Option::is_some) {
+            Termination::Error
+        } else {
+            Termination::Halted
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Report {
+    pub termination: Termination,
+    pub sent: Vec<usize>,
+    pub errors: Vec<Option<ExecError>>,
+}
+
+/// Run `computer` until it blocks, halts or fails. Also returns whether it
+/// executed anything at all.
+fn turn<T: Io>(computer: &mut Computer<T>) -> (bool, Result<Status, ExecError>) {
+    let mut stepped = false;
+    loop {
+        match computer.try_step() {
+            Ok(Status::Running) => stepped = true,
+            status => return (stepped, status),
+        }
+    }
+}
+
+/// Give every machine a turn, again and again, until a full round goes by
+/// without a single instruction executed: then nobody can ever make progress
+/// again. `after_turn` is called with the index of every machine that just had
+/// its turn.
+pub(super) fn round_robin<T: Io>(
+    computers: &mut [Computer<T>],
+    mut after_turn: impl FnMut(&mut [Computer<T>], usize),
+) -> Report {
+    let mut errors = vec![None; computers.len()];
+    let mut blocked = vec![false; computers.len()];
+
+    loop {
+        let mut progress = false;
+
+        for idx in 0..computers.len() {
+            if errors[idx].is_some() {
+                continue;
+            }
+
+            let (stepped, status) = turn(&mut computers[idx]);
+            progress |= stepped;
+            match status {
+                Ok(status) => blocked[idx] = status == Status::Blocked,
+                Err(e) => {
+                    errors[idx] = Some(e);
+                    blocked[idx] = false;
+                }
+            }
+            after_turn(computers, idx);
+        }
+
+        if !progress {
+            break;
+        }
+    }
+
+    Report {
+        termination: Termination::new(&blocked, &errors),
+        sent: computers.iter().map(Computer::sent).collect(),
+        errors,
+    }
+}
+
+/// Runs a group of `Computer`s cooperatively on the current thread, in a
+/// round-robin fashion. Each machine runs until it blocks or halts, then the
+/// next one gets its turn.
+#[derive(Debug)]
+pub struct Scheduler<T = Channel> {
+    computers: Vec<Computer<T>>,
+}
+
+impl<T: Io> Scheduler<T> {
+    pub fn new(computers: Vec<Computer<T>>) -> Self {
+        Scheduler { computers }
+    }
+
+    pub fn computers(&self) -> &[Computer<T>] {
+        &self.computers
+    }
+
+    pub fn into_computers(self) -> Vec<Computer<T>> {
+        self.computers
+    }
+
+    pub fn run(&mut self) -> Report {
+        round_robin(&mut self.computers, |_, _| ())
+    }
+}
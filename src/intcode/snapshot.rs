@@ -0,0 +1,246 @@
+use super::arithmetic::{ArithmeticMode, ModuloMode};
+use super::io::{Io, IoError};
+use super::{Computer, Register, Registers, Semantics, Value};
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::str::FromStr;
+
+const HEADER: &str = "intcode snapshot v1";
+
+/// Everything needed to resume a `Computer` exactly where it was, except its
+/// program.
+///
+/// The text format is one `key value` pair per line, after a header line.
+/// Registers that are zero are omitted:
+/// ```text
+/// intcode snapshot v1
+/// pc 12
+/// sent 3
+/// semantics duet
+/// arithmetic checked
+/// modulo truncated
+/// last_played 0
+/// recovered none
+/// register a 5
+/// register p 1
+/// input 7 -2
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Snapshot {
+    pub registers: Registers,
+    pub instruction_pointer: usize,
+    /// Values waiting to be received.
+    pub input: Vec<Value>,
+    pub sent: usize,
+    pub semantics: Semantics,
+    pub last_played: Value,
+    pub recovered: Option<Value>,
+    pub arithmetic_mode: ArithmeticMode,
+    pub modulo_mode: ModuloMode,
+    /// Registers whose value does not fit in a `Value`.
+    #[cfg(feature = "bigint")]
+    pub big: std::collections::BTreeMap<Register, num_bigint::BigInt>,
+}
+
+impl Display for Snapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "pc {}", self.instruction_pointer)?;
+        writeln!(f, "sent {}", self.sent)?;
+        writeln!(
+            f,
+            "semantics {}",
+            match self.semantics {
+                Semantics::Sound => "sound",
+                Semantics::Duet => "duet",
+            }
+        )?;
+        writeln!(
+            f,
+            "arithmetic {}",
+            match self.arithmetic_mode {
+                ArithmeticMode::Wrapping => "wrapping",
+                ArithmeticMode::Checked => "checked",
+                ArithmeticMode::Saturating => "saturating",
+                #[cfg(feature = "bigint")]
+                ArithmeticMode::Big => "big",
+            }
+        )?;
+        writeln!(
+            f,
+            "modulo {}",
+            match self.modulo_mode {
+                ModuloMode::Truncated => "truncated",
+                ModuloMode::Euclidean => "euclidean",
+            }
+        )?;
+        writeln!(f, "last_played {}", self.last_played)?;
+        match self.recovered {
+            Some(value) => writeln!(f, "recovered {}", value)?,
+            None => writeln!(f, "recovered none")?,
+        }
+        for (register, value) in self.registers.non_zero() {
+            #[cfg(feature = "bigint")]
+            if let Some(value) = self.big.get(&register) {
+                writeln!(f, "register {} {}", register, value)?;
+                continue;
+            }
+            writeln!(f, "register {} {}", register, value)?;
+        }
+        write!(f, "input")?;
+        for value in &self.input {
+            write!(f, " {}", value)?;
+        }
+        writeln!(f)
+    }
+}
+
+/// The value of a `key value` line.
+fn parse_field<T>(value: Option<&str>, line: &str) -> Result<T, String>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value
+        .ok_or_else(|| format!("Missing value: {}", line))?
+        .parse()
+        .map_err(|e| format!("{}: {}", e, line))
+}
+
+impl FromStr for Snapshot {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines();
+        match lines.next().map(str::trim) {
+            Some(HEADER) => {}
+            Some(header) if header.starts_with("intcode snapshot ") => {
+                return Err(format!("Unsupported version: {}", header))
+            }
+            _ => return Err(format!("Missing header: {}", HEADER)),
+        }
+
+        let mut seen = BTreeSet::new();
+        let mut snapshot = Snapshot::default();
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let mut words = line.split_whitespace();
+            let key = words.next().unwrap_or_default();
+            let value = words.next();
+
+            match key {
+                "pc" => snapshot.instruction_pointer = parse_field(value, line)?,
+                "sent" => snapshot.sent = parse_field(value, line)?,
+                "semantics" => {
+                    snapshot.semantics = match value {
+                        Some("sound") => Semantics::Sound,
+                        Some("duet") => Semantics::Duet,
+                        _ => return Err(format!("Unknown semantics: {}", line)),
+                    }
+                }
+                "arithmetic" => {
+                    snapshot.arithmetic_mode = match value {
+                        Some("wrapping") => ArithmeticMode::Wrapping,
+                        Some("checked") => ArithmeticMode::Checked,
+                        Some("saturating") => ArithmeticMode::Saturating,
+                        #[cfg(feature = "bigint")]
+                        Some("big") => ArithmeticMode::Big,
+                        _ => return Err(format!("Unknown arithmetic mode: {}", line)),
+                    }
+                }
+                "modulo" => {
+                    snapshot.modulo_mode = match value {
+                        Some("truncated") => ModuloMode::Truncated,
+                        Some("euclidean") => ModuloMode::Euclidean,
+                        _ => return Err(format!("Unknown modulo mode: {}", line)),
+                    }
+                }
+                "last_played" => snapshot.last_played = parse_field(value, line)?,
+                "recovered" => {
+                    snapshot.recovered = match value {
+                        Some("none") => None,
+                        _ => Some(parse_field(value, line)?),
+                    }
+                }
+                "register" => {
+                    let register: Register = value
+                        .ok_or_else(|| format!("Missing register: {}", line))?
+                        .parse()
+                        .map_err(|e| format!("{}: {}", e, line))?;
+                    if !seen.insert(register) {
+                        return Err(format!("Duplicate register: {}", line));
+                    }
+                    let value = words.next();
+                    snapshot.registers[register] = match parse_field::<Value>(value, line) {
+                        Ok(value) => value,
+                        #[cfg(feature = "bigint")]
+                        Err(error) => {
+                            let big: num_bigint::BigInt =
+                                value.and_then(|v| v.parse().ok()).ok_or(error)?;
+                            let saturated = match big.sign() {
+                                num_bigint::Sign::Minus => Value::MIN,
+                                _ => Value::MAX,
+                            };
+                            snapshot.big.insert(register, big);
+                            saturated
+                        }
+                        #[cfg(not(feature = "bigint"))]
+                        Err(error) => return Err(error),
+                    };
+                }
+                "input" => {
+                    snapshot.input = value
+                        .into_iter()
+                        .chain(words.by_ref())
+                        .map(|v| parse_field(Some(v), line))
+                        .collect::<Result<_, _>>()?;
+                }
+                _ => return Err(format!("Unknown key: {}", line)),
+            }
+
+            if words.next().is_some() {
+                return Err(format!("Trailing data: {}", line));
+            }
+        }
+
+        Ok(snapshot)
+    }
+}
+
+impl<T: Io> Computer<T> {
+    /// Needs `&mut self`, since some kinds of I/O can only look at what is
+    /// pending by taking it out of where it was.
+    pub fn snapshot(&mut self) -> Result<Snapshot, IoError> {
+        Ok(Snapshot {
+            registers: self.registers,
+            instruction_pointer: self.instruction_pointer,
+            input: self.io.pending()?,
+            sent: self.sent,
+            semantics: self.semantics,
+            last_played: self.last_played,
+            recovered: self.recovered,
+            arithmetic_mode: self.arithmetic_mode,
+            modulo_mode: self.modulo_mode,
+            #[cfg(feature = "bigint")]
+            big: self.big.clone(),
+        })
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), IoError> {
+        self.io.set_pending(&snapshot.input)?;
+        self.registers = snapshot.registers;
+        self.instruction_pointer = snapshot.instruction_pointer;
+        self.sent = snapshot.sent;
+        self.semantics = snapshot.semantics;
+        self.last_played = snapshot.last_played;
+        self.recovered = snapshot.recovered;
+        self.arithmetic_mode = snapshot.arithmetic_mode;
+        self.modulo_mode = snapshot.modulo_mode;
+        #[cfg(feature = "bigint")]
+        {
+            self.big = snapshot.big.clone();
+        }
+        // Whatever happened before no longer leads to this state.
+        self.clear_history();
+        Ok(())
+    }
+}
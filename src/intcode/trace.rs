@@ -0,0 +1,367 @@
+use super::dialect::Behavior;
+use super::io::Io;
+use super::{Computer, Instruction, ParseError, Register, Value};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// One executed instruction, with the registers it wrote (and their new
+/// values) and what it sent or received.
+///
+/// The line format puts the effects after a `|`, with `>` for a value sent
+/// and `<` for a value received. Superinstructions are marked as `(fused)`:
+/// ```text
+/// 41 12: mul g e | g=-4
+/// 42 13: snd p | >1
+/// 43 14: rcv a | a=7 <7
+/// 44 15: jnz g 2 |
+/// 45 16: mac a b c (fused) | a=6 c=0
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Record {
+    pub step: usize,
+    pub pc: usize,
+    /// As printed, since superinstructions cannot be parsed back.
+    pub instruction: String,
+    /// Whether this is a superinstruction, which stands for several steps of
+    /// the original program.
+    pub fused: bool,
+    pub writes: Vec<(Register, Value)>,
+    pub sent: Option<Value>,
+    pub received: Option<Value>,
+}
+
+impl Display for Record {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}: {}", self.step, self.pc, self.instruction)?;
+        if self.fused {
+            write!(f, " {}", FUSED)?;
+        }
+        write!(f, " |")?;
+        for (register, value) in &self.writes {
+            write!(f, " {}={}", register, value)?;
+        }
+        if let Some(value) = self.sent {
+            write!(f, " >{}", value)?;
+        }
+        if let Some(value) = self.received {
+            write!(f, " <{}", value)?;
+        }
+        Ok(())
+    }
+}
+
+const FUSED: &str = "(fused)";
+
+fn number<N: FromStr>(word: &str) -> Result<N, String> {
+    word.parse()
+        .map_err(|_| format!("Invalid number: {}", word))
+}
+
+impl FromStr for Record {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (head, effects) = s
+            .split_once('|')
+            .ok_or_else(|| format!("Missing `|`: {}", s))?;
+        let (position, instruction) = head
+            .split_once(':')
+            .ok_or_else(|| format!("Missing `:`: {}", s))?;
+        let (step, pc) = position
+            .trim()
+            .split_once(' ')
+            .ok_or_else(|| format!("Expected a step and a program counter: {}", s))?;
+
+        let instruction = instruction.trim();
+        let (instruction, fused) = match instruction.strip_suffix(FUSED) {
+            Some(instruction) => (instruction.trim_end(), true),
+            None => (instruction, false),
+        };
+        let mut record = Record {
+            step: number(step)?,
+            pc: number(pc.trim())?,
+            instruction: instruction.to_string(),
+            fused,
+            writes: Vec::new(),
+            sent: None,
+            received: None,
+        };
+        for word in effects.split_whitespace() {
+            if let Some(value) = word.strip_prefix('>') {
+                record.sent = Some(number(value)?);
+            } else if let Some(value) = word.strip_prefix('<') {
+                record.received = Some(number(value)?);
+            } else if let Some((register, value)) = word.split_once('=') {
+                let register = register.parse().map_err(|e: ParseError| e.to_string())?;
+                record.writes.push((register, number(value)?));
+            } else {
+                return Err(format!("Unknown effect: {}", word));
+            }
+        }
+
+        Ok(record)
+    }
+}
+
+/// Where two traces first disagree. The step numbers themselves are not
+/// compared, only the position in each trace.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Divergence {
+    /// Index of the first record that differs, in the left trace.
+    pub index: usize,
+    /// The same in the right trace, which is only different from `index`
+    /// when comparing effects.
+    pub right_index: usize,
+    /// `None` if that trace ended first.
+    pub left: Option<Record>,
+    pub right: Option<Record>,
+}
+
+impl Divergence {
+    /// The names of the fields that differ, or `end` if a trace ended.
+    pub fn fields(&self) -> Vec<&'static str> {
+        match (&self.left, &self.right) {
+            (Some(left), Some(right)) => [
+                ("pc", left.pc != right.pc),
+                ("instruction", left.instruction != right.instruction),
+                ("writes", left.writes != right.writes),
+                ("sent", left.sent != right.sent),
+                ("received", left.received != right.received),
+            ]
+            .into_iter()
+            .filter(|&(_, differs)| differs)
+            .map(|(field, _)| field)
+            .collect(),
+            _ => vec!["end"],
+        }
+    }
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.index == self.right_index {
+            write!(f, "First divergence at record {}", self.index)?;
+        } else {
+            write!(
+                f,
+                "First divergence at records {} and {}",
+                self.index, self.right_index
+            )?;
+        }
+        writeln!(f, " ({}):", self.fields().join(", "))?;
+        for (side, record) in [('<', &self.left), ('>', &self.right)] {
+            match record {
+                Some(record) => writeln!(f, "{} {}", side, record)?,
+                None => writeln!(f, "{} (end of trace)", side)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn same(left: &Record, right: &Record) -> bool {
+    (
+        left.pc,
+        &left.instruction,
+        &left.writes,
+        left.sent,
+        left.received,
+    ) == (
+        right.pc,
+        &right.instruction,
+        &right.writes,
+        right.sent,
+        right.received,
+    )
+}
+
+/// `None` if both traces record exactly the same execution.
+pub fn diff(left: &[Record], right: &[Record]) -> Option<Divergence> {
+    let index = left
+        .iter()
+        .zip(right)
+        .position(|(left, right)| !same(left, right))
+        .unwrap_or(left.len().min(right.len()));
+    if index == left.len() && index == right.len() {
+        return None;
+    }
+
+    Some(Divergence::at(left, index, right, index))
+}
+
+impl Divergence {
+    fn at(left: &[Record], index: usize, right: &[Record], right_index: usize) -> Self {
+        Divergence {
+            index,
+            right_index,
+            left: left.get(index).cloned(),
+            right: right.get(right_index).cloned(),
+        }
+    }
+}
+
+/// Everything a trace did up to some point.
+#[derive(Debug, Default, Clone)]
+struct Effects {
+    /// The last value written to each register.
+    registers: BTreeMap<Register, Value>,
+    sent: Vec<Value>,
+    received: Vec<Value>,
+}
+
+impl Effects {
+    fn apply(&mut self, record: &Record) {
+        self.registers.extend(record.writes.iter().copied());
+        self.sent.extend(record.sent);
+        self.received.extend(record.received);
+    }
+
+    /// Apply records from `idx` on, until reaching a record at `pc` (or the
+    /// end of the trace, for `None`).
+    fn catch_up(&mut self, trace: &[Record], idx: &mut usize, pc: Option<usize>) {
+        while let Some(record) = trace.get(*idx).filter(|record| Some(record.pc) != pc) {
+            self.apply(record);
+            *idx += 1;
+        }
+    }
+}
+
+/// Both traces' effects, and what comparing them so far implies.
+#[derive(Debug, Default)]
+struct Comparison {
+    left: Effects,
+    right: Effects,
+    /// A register only one side wrote to still has its initial value on the
+    /// other side, so that is what the initial value must have been.
+    initial: BTreeMap<Register, Value>,
+    /// How many values sent and received are known to be the same.
+    compared: (usize, usize),
+}
+
+impl Comparison {
+    fn agrees(&mut self) -> bool {
+        let (left, right) = (&self.left, &self.right);
+        // Only the values since the last comparison can differ.
+        let (sent, received) = self.compared;
+        if left.sent[sent..] != right.sent[sent..]
+            || left.received[received..] != right.received[received..]
+        {
+            return false;
+        }
+        self.compared = (left.sent.len(), left.received.len());
+
+        for register in left.registers.keys().chain(right.registers.keys()) {
+            let agrees = match (left.registers.get(register), right.registers.get(register)) {
+                (Some(left), Some(right)) => left == right,
+                (Some(value), None) | (None, Some(value)) => {
+                    self.initial.entry(*register).or_insert(*value) == value
+                }
+                (None, None) => true,
+            };
+            if !agrees {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Like `diff`, but only compares what both traces did (registers written,
+/// values sent and received) wherever both reach the same program counter.
+/// A superinstruction is matched with as many records of the other trace as
+/// it takes to reach the same program counter, so that the trace of an
+/// optimized program can be compared with the trace of the original.
+pub fn diff_effects(left: &[Record], right: &[Record]) -> Option<Divergence> {
+    let pc = |trace: &[Record], idx: usize| trace.get(idx).map(|record| record.pc);
+    let (mut i, mut j) = (0, 0);
+    let mut comparison = Comparison::default();
+
+    loop {
+        let (a, b) = match (left.get(i), right.get(j)) {
+            (None, None) => return None,
+            (Some(a), Some(b)) if a.pc == b.pc => (a, b),
+            _ => return Some(Divergence::at(left, i, right, j)),
+        };
+        let (start_i, start_j) = (i, j);
+        comparison.left.apply(a);
+        comparison.right.apply(b);
+        i += 1;
+        j += 1;
+
+        match (a.fused, b.fused) {
+            (true, false) => comparison.right.catch_up(right, &mut j, pc(left, i)),
+            (false, true) => comparison.left.catch_up(left, &mut i, pc(right, j)),
+            _ => (),
+        }
+        if pc(left, i) != pc(right, j) || !comparison.agrees() {
+            return Some(Divergence::at(left, start_i, right, start_j));
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub(super) struct Tracer {
+    steps: usize,
+    records: Vec<Record>,
+}
+
+impl Tracer {
+    pub(super) fn record(
+        &mut self,
+        pc: usize,
+        instruction: Instruction,
+        writes: Vec<(Register, Value)>,
+        sent: Option<Value>,
+        received: Option<Value>,
+    ) {
+        self.records.push(Record {
+            step: self.steps,
+            pc,
+            instruction: instruction.to_string(),
+            fused: matches!(instruction, Instruction::Fused(_)),
+            writes,
+            sent,
+            received,
+        });
+        self.steps += 1;
+    }
+}
+
+impl<T: Io> Computer<T> {
+    /// Start recording a `Record` for every step.
+    pub fn enable_tracing(&mut self) {
+        self.tracer.get_or_insert_with(Tracer::default);
+    }
+
+    /// Stop recording, and forget everything recorded so far.
+    pub fn disable_tracing(&mut self) {
+        self.tracer = None;
+    }
+
+    pub fn trace(&self) -> &[Record] {
+        self.tracer
+            .as_ref()
+            .map_or(&[], |tracer| tracer.records.as_slice())
+    }
+
+    /// Everything recorded so far. Steps are still numbered from when tracing
+    /// was enabled.
+    pub fn take_trace(&mut self) -> Vec<Record> {
+        self.tracer
+            .as_mut()
+            .map(|tracer| std::mem::take(&mut tracer.records))
+            .unwrap_or_default()
+    }
+
+    /// The value the current instruction would send, if it sends one.
+    pub(super) fn sending(&self) -> Option<Value> {
+        match self.current_instruction()? {
+            Instruction::Send(x) => Some(self.get_value(x)),
+            Instruction::Extended(opcode, x, _) if matches!(opcode.behavior, Behavior::Send) => {
+                Some(self.get_value(x))
+            }
+            _ => None,
+        }
+    }
+}
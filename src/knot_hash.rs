@@ -27,7 +27,7 @@ impl KnotHash {
     }
 
     pub fn hash(&mut self, lengths: impl Iterator<Item = u8>) {
-        let lengths: Vec<u8> = lengths.chain(SUFFIX.into_iter()).collect();
+        let lengths: Vec<u8> = lengths.chain(SUFFIX).collect();
 
         for _ in 0..NBR_ROUNDS {
             self.round(lengths.iter().copied());
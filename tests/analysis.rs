@@ -0,0 +1,121 @@
+use advent_of_code_2017::intcode::analysis::{ControlFlowGraph, Edge, EdgeKind, Target};
+use std::collections::BTreeSet;
+
+mod common;
+
+use common::{parse, PRIME_SEARCH};
+
+fn jump(block: usize) -> Edge {
+    Edge {
+        kind: EdgeKind::Jump,
+        target: Target::Block(block),
+    }
+}
+
+fn fallthrough(block: usize) -> Edge {
+    Edge {
+        kind: EdgeKind::Fallthrough,
+        target: Target::Block(block),
+    }
+}
+
+#[test]
+fn basic_blocks() {
+    let cfg = ControlFlowGraph::new(&parse(PRIME_SEARCH));
+    let bounds: Vec<(usize, usize)> = cfg
+        .blocks
+        .iter()
+        .map(|block| (block.start, block.end))
+        .collect();
+    assert_eq!(
+        bounds,
+        [
+            (0, 3),
+            (3, 4),
+            (4, 8),
+            (8, 10),
+            (10, 11),
+            (11, 15),
+            (15, 16),
+            (16, 20),
+            (20, 24),
+            (24, 25),
+            (25, 26),
+            (26, 29),
+            (29, 30),
+            (30, 32),
+        ]
+    );
+    assert!(cfg.blocks.iter().all(|block| !block.dynamic));
+
+    // `jnz a 2` may go either way.
+    assert_eq!(cfg.blocks[0].successors, [jump(2), fallthrough(1)]);
+    // `jnz 1 3` always jumps, here out of the program.
+    assert_eq!(
+        cfg.blocks[12].successors,
+        [Edge {
+            kind: EdgeKind::Jump,
+            target: Target::Exit,
+        }]
+    );
+}
+
+#[test]
+fn outer_loop() {
+    let cfg = ControlFlowGraph::new(&parse(PRIME_SEARCH));
+    // `sub b -17` and `jnz 1 -23`, which always jumps back to `set f 1`.
+    let latch = cfg.block_of(31).unwrap();
+    assert_eq!(cfg.block_of(30), Some(latch));
+    assert_eq!(cfg.blocks[latch].successors, [jump(3)]);
+    assert_eq!(cfg.blocks[3].start, 8);
+    assert_eq!(cfg.successors(latch).collect::<Vec<_>>(), [3]);
+    assert_eq!(cfg.predecessors(latch), [11]);
+}
+
+#[test]
+fn nested_loops() {
+    let loops = ControlFlowGraph::new(&parse(PRIME_SEARCH)).loops();
+    let summary: Vec<_> = loops
+        .iter()
+        .map(|l| (l.header, l.latches.clone(), l.parent, l.depth))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (3, BTreeSet::from([13]), None, 1),
+            (4, BTreeSet::from([8]), Some(0), 2),
+            (5, BTreeSet::from([7]), Some(1), 3),
+        ]
+    );
+    // The innermost loop is `set g d` to `jnz g -8`, with `set f 0` in it.
+    assert_eq!(loops[2].blocks, BTreeSet::from([5, 6, 7]));
+    assert_eq!(loops[1].blocks, BTreeSet::from([4, 5, 6, 7, 8]));
+    // Counting `h` is in the outer loop only, and leaving it is not.
+    assert!(loops[0].blocks.is_superset(&BTreeSet::from([9, 10, 11])));
+    assert!(!loops[0].blocks.contains(&12));
+}
+
+#[test]
+fn dynamic_jumps() {
+    let cfg = ControlFlowGraph::new(&parse("set a 2\njgz a b\nadd a 1\njnz a -3\njnz 1 5"));
+    assert!(cfg.blocks[0].dynamic);
+    // Where it jumps is unknown, so only the fallthrough is an edge.
+    assert_eq!(cfg.blocks[0].successors, [fallthrough(1)]);
+    assert!(!cfg.blocks[1].dynamic);
+
+    assert_eq!(
+        cfg.to_dot(),
+        r#"digraph cfg {
+    node [shape=box, fontname=monospace];
+    b0 [label="0: set a 2\l1: jgz a b\l", penwidth=2, style=dashed];
+    b1 [label="2: add a 1\l3: jnz a -3\l"];
+    b2 [label="4: jnz 1 5\l"];
+    exit [shape=doublecircle];
+    b0 -> b1;
+    b1 -> b0 [label="jump"];
+    b1 -> b2;
+    b2 -> exit [label="jump"];
+}
+"#
+    );
+}
@@ -0,0 +1,96 @@
+use advent_of_code_2017::intcode::assembler::{assemble, assemble_with, line_numbers};
+use advent_of_code_2017::intcode::dialect::{Dialect, JLZ};
+
+mod common;
+
+use common::parse;
+
+/// Every diagnostic, as printed.
+fn diagnostics(source: &str) -> Vec<String> {
+    match assemble(source) {
+        Ok(instructions) => panic!("Assembled: {:?}", instructions),
+        Err(diagnostics) => diagnostics.iter().map(ToString::to_string).collect(),
+    }
+}
+
+#[test]
+fn labels() {
+    let source = "# Count down from 10, then skip setting b.
+        set a 10
+again:  sub a 1     # Jumped to from below.
+        jnz a again
+        jgz 1 done  # Defined further down.
+        set b 1
+done:";
+    assert_eq!(
+        assemble(source),
+        Ok(parse("set a 10\nsub a 1\njnz a -1\njgz 1 2\nset b 1"))
+    );
+    assert_eq!(line_numbers(source), [2, 3, 4, 5, 6]);
+
+    // Several labels for the same instruction, and one used by itself.
+    let source = "one: two:\nthree: jnz 1 two\njnz 1 one\njnz 1 three";
+    assert_eq!(assemble(source), Ok(parse("jnz 1 0\njnz 1 -1\njnz 1 -2")));
+
+    // Only jumps take labels, including those of extra opcodes.
+    let dialect = Dialect::coprocessor().with(&JLZ);
+    assert_eq!(
+        assemble_with("top: sub a 1\njlz a top", &dialect),
+        assemble_with("sub a 1\njlz a -1", &dialect)
+    );
+}
+
+#[test]
+fn label_errors() {
+    assert_eq!(
+        diagnostics(
+            "start: set a 1
+start: add a 1
+b: sub a 1
+  jnz a nowhere
+2x: mul a 2
+   c:"
+        ),
+        [
+            "2:1: Duplicate label: start",
+            "3:1: Label is named like a register: b",
+            "4:9: Unknown label: nowhere",
+            "5:1: Invalid label name: 2x",
+            "6:4: Label is named like a register: c",
+        ]
+    );
+    // Not a jump, so not a label either.
+    assert_eq!(
+        diagnostics("start: set a start"),
+        ["1:14: Invalid parameter: start"]
+    );
+}
+
+#[test]
+fn instruction_errors() {
+    assert_eq!(
+        diagnostics(
+            "set a
+  nop a  # Comments do not count.
+
+\tadd 5 a
+jnz a 2 3
+set ab 1"
+        ),
+        [
+            "1:6: Missing second parameter.",
+            "2:3: Unknown operation: nop",
+            "4:6: Unknown register: 5",
+            "5:9: Unexpected parameter: 3",
+            "6:5: Unknown register: ab",
+        ]
+    );
+    assert_eq!(
+        assemble_with("mod a 2\njnz a -1", &Dialect::duet())
+            .unwrap_err()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        ["2:1: Operation not allowed in this dialect: jnz"]
+    );
+}
@@ -0,0 +1,181 @@
+use advent_of_code_2017::intcode::asynchronous::{pipe, AsyncComputer, Pipe, PipeReceiver};
+use advent_of_code_2017::intcode::io::IoError;
+use advent_of_code_2017::intcode::{ExecError, Value};
+use std::future::Future;
+use std::pin::pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+mod common;
+
+use common::{parse, register, DUET};
+
+/// Counts how many times it was woken, so that futures can be polled by
+/// hand, without an executor.
+#[derive(Default)]
+struct Counter(AtomicUsize);
+
+impl Wake for Counter {
+    fn wake(self: Arc<Self>) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn waker() -> (Arc<Counter>, Waker) {
+    let counter = Arc::new(Counter::default());
+    (counter.clone(), Waker::from(counter))
+}
+
+fn wakes(counter: &Counter) -> usize {
+    counter.0.load(Ordering::SeqCst)
+}
+
+#[test]
+fn send_then_receive() {
+    let (sender, mut receiver) = pipe();
+    sender.send(1);
+    sender.send(2);
+
+    let (counter, waker) = waker();
+    let mut cx = Context::from_waker(&waker);
+    assert_eq!(pin!(receiver.receive()).poll(&mut cx), Poll::Ready(Ok(1)));
+    assert_eq!(pin!(receiver.receive()).poll(&mut cx), Poll::Ready(Ok(2)));
+    assert_eq!(receiver.try_receive(), Ok(None));
+    assert_eq!(wakes(&counter), 0);
+}
+
+#[test]
+fn receive_then_send() {
+    let (sender, mut receiver) = pipe();
+    let (counter, waker) = waker();
+    let mut cx = Context::from_waker(&waker);
+
+    let mut receive = pin!(receiver.receive());
+    assert_eq!(receive.as_mut().poll(&mut cx), Poll::Pending);
+    assert_eq!(wakes(&counter), 0);
+
+    sender.send(7);
+    assert_eq!(wakes(&counter), 1);
+    assert_eq!(receive.as_mut().poll(&mut cx), Poll::Ready(Ok(7)));
+}
+
+#[test]
+fn dropping_every_sender_disconnects() {
+    let (sender, mut receiver) = pipe();
+    let other = sender.clone();
+    let (counter, waker) = waker();
+    let mut cx = Context::from_waker(&waker);
+
+    let mut receive = pin!(receiver.receive());
+    assert_eq!(receive.as_mut().poll(&mut cx), Poll::Pending);
+
+    other.send(3);
+    drop(other);
+    drop(sender);
+    assert_eq!(wakes(&counter), 1);
+    // What was sent before is still received.
+    assert_eq!(receive.as_mut().poll(&mut cx), Poll::Ready(Ok(3)));
+    assert_eq!(
+        receive.as_mut().poll(&mut cx),
+        Poll::Ready(Err(IoError::Disconnected))
+    );
+
+    // The last sender going away wakes a receiver already waiting.
+    let (sender, mut receiver) = pipe();
+    let mut receive = pin!(receiver.receive());
+    assert_eq!(receive.as_mut().poll(&mut cx), Poll::Pending);
+    drop(sender);
+    assert_eq!(wakes(&counter), 2);
+    assert_eq!(
+        receive.as_mut().poll(&mut cx),
+        Poll::Ready(Err(IoError::Disconnected))
+    );
+}
+
+/// Polls the receiver as soon as it is woken, from inside `wake`, like an
+/// executor that runs tasks inline.
+struct Inline {
+    receiver: Mutex<PipeReceiver>,
+    received: Mutex<Vec<Result<Value, IoError>>>,
+}
+
+impl Inline {
+    fn poll(&self, waker: &Waker) -> Poll<Result<Value, IoError>> {
+        let mut receiver = self.receiver.lock().unwrap();
+        pin!(receiver.receive()).poll(&mut Context::from_waker(waker))
+    }
+}
+
+impl Wake for Inline {
+    fn wake(self: Arc<Self>) {
+        if let Poll::Ready(result) = self.poll(Waker::noop()) {
+            self.received.lock().unwrap().push(result);
+        }
+    }
+}
+
+#[test]
+fn waking_polls_inline() {
+    let (sender, receiver) = pipe();
+    let inline = Arc::new(Inline {
+        receiver: Mutex::new(receiver),
+        received: Mutex::new(Vec::new()),
+    });
+    let waker = Waker::from(inline.clone());
+
+    assert_eq!(inline.poll(&waker), Poll::Pending);
+    sender.send(4);
+    assert_eq!(inline.poll(&waker), Poll::Pending);
+    drop(sender);
+    assert_eq!(
+        *inline.received.lock().unwrap(),
+        [Ok(4), Err(IoError::Disconnected)]
+    );
+}
+
+#[test]
+fn day18_part2_example() {
+    let (to_1, from_0) = pipe();
+    let (to_0, from_1) = pipe();
+    let mut programs = [
+        AsyncComputer::new(parse(DUET), Pipe::new(to_1, from_1)),
+        AsyncComputer::new(parse(DUET), Pipe::new(to_0, from_0)),
+    ];
+    programs[1].computer_mut().set_register(register('p'), 1);
+    let (counter, waker) = waker();
+    let mut cx = Context::from_waker(&waker);
+    {
+        let [zero, one] = &mut programs;
+        let mut zero = pin!(zero.run_until_halt(Some(1000)));
+        let mut one = pin!(one.run_until_halt(Some(1000)));
+
+        // Both send 3 values, receive them, and then wait on each other forever.
+        for _ in 0..10 {
+            assert!(zero.as_mut().poll(&mut cx).is_pending());
+            assert!(one.as_mut().poll(&mut cx).is_pending());
+        }
+    }
+    // Only program 0 ever waited for a value that then arrived.
+    assert_eq!(wakes(&counter), 1);
+
+    for program in &programs {
+        assert_eq!(program.computer().sent(), 3);
+        assert_eq!(program.computer().instruction_pointer(), 6);
+    }
+    for (program, p) in programs.iter().zip([1, 0]) {
+        let registers = ['a', 'b', 'c'].map(|name| program.computer().register(register(name)));
+        assert_eq!(registers, [1, 2, p]);
+    }
+
+    // Once the other program is gone, the waiting one stops.
+    let [zero, mut one] = programs;
+    drop(zero);
+    let mut run = pin!(one.run_until_halt(None));
+    match run.as_mut().poll(&mut cx) {
+        Poll::Ready(halt) => {
+            assert_eq!(halt.error, Some(ExecError::from(IoError::Disconnected)))
+        }
+        Poll::Pending => panic!("Still waiting for a disconnected program."),
+    }
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "bigint")]
+
+use advent_of_code_2017::intcode::arithmetic::ArithmeticMode;
+use advent_of_code_2017::intcode::io::Queue;
+use advent_of_code_2017::intcode::{Computer, ExecError, Value};
+use num_bigint::BigInt;
+
+mod common;
+
+use common::{parse, register};
+
+fn big(program: &str) -> Computer<Queue> {
+    let mut computer = Computer::with_io(parse(program), Queue::default());
+    computer.set_arithmetic_mode(ArithmeticMode::Big);
+    computer
+}
+
+#[test]
+fn overflow_into_big() {
+    let mut computer = big("set a 9223372036854775807\nadd a 1\nmul a -2");
+    assert_eq!(computer.run_until_halt(None).error, None);
+    let expected = (BigInt::from(Value::MAX) + 1) * -2;
+    assert_eq!(computer.big_register(register('a')), expected);
+    // Read as a plain value, it keeps its sign.
+    assert_eq!(computer.register(register('a')), Value::MIN);
+}
+
+#[test]
+fn modulo_back_to_small() {
+    let mut computer = big("set a 9223372036854775807\nmul a a\nset b a\nmod a 1000");
+    assert_eq!(computer.run_until_halt(None).error, None);
+    // (2^63 - 1)^2 = 85070591730234615847396907784232501249
+    assert_eq!(computer.register(register('a')), 249);
+    assert_eq!(computer.big_register(register('a')), BigInt::from(249));
+    assert_eq!(
+        computer.big_register(register('b')).to_string(),
+        "85070591730234615847396907784232501249"
+    );
+}
+
+#[test]
+fn big_values_cannot_leave() {
+    let mut computer = big("set a 9223372036854775807\nadd a 1\nsnd a");
+    let halt = computer.run_until_halt(None);
+    assert_eq!(halt.error, Some(ExecError::Overflow));
+    assert_eq!(halt.instruction_pointer, 2);
+    assert!(computer.io().output.is_empty());
+
+    // Nor be used to jump, even though they are known to be positive.
+    for jump in ["jnz 1 a", "jgz 1 a"] {
+        let program = format!("set a 9223372036854775807\nadd a 1\n{}", jump);
+        let halt = big(&program).run_until_halt(None);
+        assert_eq!(halt.error, Some(ExecError::Overflow));
+        assert_eq!(halt.instruction_pointer, 2);
+    }
+
+    // As a condition, only the sign matters.
+    let mut computer = big("set a 9223372036854775807\nadd a 1\njgz a 2\nset b 1");
+    assert_eq!(computer.run_until_halt(None).error, None);
+    assert_eq!(computer.register(register('b')), 0);
+}
+
+#[test]
+fn step_back_across_a_big_write() {
+    let mut computer = big("set a 9223372036854775807\nadd a 1\nset a 5");
+    computer.enable_history();
+    assert_eq!(computer.run_until_halt(None).error, None);
+    assert_eq!(computer.big_register(register('a')), BigInt::from(5));
+
+    assert_eq!(computer.step_back(), Ok(true));
+    assert_eq!(
+        computer.big_register(register('a')),
+        BigInt::from(Value::MAX) + 1
+    );
+    assert_eq!(computer.step_back(), Ok(true));
+    assert_eq!(
+        computer.big_register(register('a')),
+        BigInt::from(Value::MAX)
+    );
+    assert_eq!(computer.register(register('a')), Value::MAX);
+}
@@ -0,0 +1,97 @@
+use advent_of_code_2017::intcode::budget::Outcome;
+use advent_of_code_2017::intcode::io::NoIo;
+use advent_of_code_2017::intcode::{Computer, ExecError};
+use std::thread;
+use std::time::Duration;
+
+mod common;
+
+use common::{parse, register};
+
+const FOREVER: &str = "add a 1\njnz 1 -1";
+
+#[test]
+fn halted() {
+    let mut computer = Computer::with_io(parse("set a 3\nadd a 1\nmul a 2"), NoIo);
+    assert_eq!(computer.run_with_budget(3), Outcome::Halted { steps: 3 });
+    assert_eq!(computer.register(register('a')), 8);
+
+    // Counting down from 3 takes 2 steps per loop.
+    let mut computer = Computer::with_io(parse("set a 3\nsub a 1\njnz a -1"), NoIo);
+    assert_eq!(computer.run_with_budget(100), Outcome::Halted { steps: 7 });
+}
+
+#[test]
+fn out_of_fuel() {
+    let mut computer = Computer::with_io(parse(FOREVER), NoIo);
+    assert_eq!(computer.run_with_budget(1000), Outcome::OutOfFuel);
+    assert_eq!(computer.register(register('a')), 500);
+
+    // The budget is per run, and running again picks up where it stopped.
+    assert_eq!(computer.run_with_budget(10), Outcome::OutOfFuel);
+    assert_eq!(computer.register(register('a')), 505);
+
+    let mut computer = Computer::with_io(parse("mod a 0"), NoIo);
+    assert_eq!(
+        computer.run_with_budget(10),
+        Outcome::Error(ExecError::DivisionByZero)
+    );
+}
+
+#[test]
+fn cancelled_from_another_thread() {
+    let mut computer = Computer::with_io(parse(FOREVER), NoIo);
+    let handle = computer.cancel_handle();
+
+    let outcome = thread::scope(|scope| {
+        let run = scope.spawn(|| computer.run_with_budget(usize::MAX));
+        thread::sleep(Duration::from_millis(20));
+        handle.cancel();
+        run.join().unwrap()
+    });
+    let steps = match outcome {
+        Outcome::Cancelled { steps } => steps,
+        outcome => panic!("Not cancelled: {:?}", outcome),
+    };
+    assert_eq!(computer.register(register('a')) as usize, steps.div_ceil(2));
+    assert!(handle.is_cancelled());
+}
+
+#[test]
+fn reset() {
+    let mut computer = Computer::with_io(parse(FOREVER), NoIo);
+    let handle = computer.cancel_handle();
+    handle.cancel();
+    assert_eq!(
+        computer.run_with_budget(10),
+        Outcome::Cancelled { steps: 0 }
+    );
+    assert_eq!(computer.register(register('a')), 0);
+
+    handle.reset();
+    assert!(!handle.is_cancelled());
+    assert_eq!(computer.run_with_budget(10), Outcome::OutOfFuel);
+    assert_eq!(computer.register(register('a')), 5);
+}
+
+#[test]
+fn clones_share_the_handle() {
+    let mut computer = Computer::with_io(parse(FOREVER), NoIo);
+    // Made before there is a handle, so it has its own.
+    let mut before = computer.clone();
+    let handle = computer.cancel_handle();
+    let mut after = computer.clone();
+
+    handle.cancel();
+    for computer in [&mut computer, &mut after] {
+        assert_eq!(
+            computer.run_with_budget(10),
+            Outcome::Cancelled { steps: 0 }
+        );
+    }
+    assert_eq!(before.run_with_budget(10), Outcome::OutOfFuel);
+
+    // Asking a clone for a handle returns the shared one.
+    after.cancel_handle().reset();
+    assert_eq!(computer.run_with_budget(10), Outcome::OutOfFuel);
+}
@@ -0,0 +1,109 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use advent_of_code_2017::intcode::{Instruction, Parameter, Register, Value};
+
+/// Day 23, with `b` and `c` lowered so that part 2 finishes quickly.
+pub const PRIME_SEARCH: &str = "set b 93
+set c b
+jnz a 2
+jnz 1 5
+mul b 2
+sub b -100
+set c b
+sub c -170
+set f 1
+set d 2
+set e 2
+set g d
+mul g e
+sub g b
+jnz g 2
+set f 0
+sub e -1
+set g e
+sub g b
+jnz g -8
+sub d -1
+set g d
+sub g b
+jnz g -13
+jnz f 2
+sub h -1
+set g b
+sub g c
+jnz g 2
+jnz 1 3
+sub b -17
+jnz 1 -23";
+
+/// The example of day 18, part 2.
+pub const DUET: &str = "snd 1
+snd 2
+snd p
+rcv a
+rcv b
+rcv c
+rcv d";
+
+/// The example of day 18, part 1.
+pub const SOUND: &str = "set a 1
+add a 2
+mul a a
+mod a 5
+snd a
+set a 0
+rcv a
+jgz a -1
+set a 1
+jgz a -2";
+
+pub fn parse(program: &str) -> Vec<Instruction> {
+    program
+        .lines()
+        .map(|line| line.parse().expect("The test programs are valid."))
+        .collect()
+}
+
+pub fn register(name: char) -> Register {
+    Register::new(name).expect("The test registers are valid.")
+}
+
+/// A small linear congruential generator, so that the random programs are
+/// the same on every run.
+pub struct Random(pub u64);
+
+impl Random {
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) % n
+    }
+
+    pub fn register(&mut self) -> Register {
+        register((b'a' + self.below(4) as u8) as char)
+    }
+
+    pub fn parameter(&mut self) -> Parameter {
+        match self.below(2) {
+            0 => Parameter::Register(self.register()),
+            _ => Parameter::Value(self.below(11) as Value - 5),
+        }
+    }
+
+    pub fn instruction(&mut self) -> Instruction {
+        let r = self.register();
+        match self.below(8) {
+            0 => Instruction::Set(r, self.parameter()),
+            1 => Instruction::Add(r, self.parameter()),
+            2 => Instruction::Sub(r, self.parameter()),
+            3 => Instruction::Multiply(r, self.parameter()),
+            4 => Instruction::Modulo(r, self.parameter()),
+            5 => Instruction::JumpGreaterThanZero(self.parameter(), self.parameter()),
+            6 => Instruction::JumpIfNotZero(self.parameter(), self.parameter()),
+            _ => Instruction::Send(self.parameter()),
+        }
+    }
+}
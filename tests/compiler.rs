@@ -0,0 +1,162 @@
+use advent_of_code_2017::intcode::arithmetic::{ArithmeticMode, ModuloMode};
+use advent_of_code_2017::intcode::io::Queue;
+use advent_of_code_2017::intcode::optimizer::optimize;
+use advent_of_code_2017::intcode::{Computer, Instruction, Semantics, Value};
+
+mod common;
+
+use common::{parse, register, Random, DUET, PRIME_SEARCH, SOUND};
+
+/// Run `computer` with both the interpreter and the compiled program, and
+/// check that they end up in exactly the same state.
+fn check(computer: Computer<Queue>, step_limit: Option<usize>) -> Computer<Queue> {
+    let mut interpreted = computer.clone();
+    let expected = interpreted.run_until_halt(step_limit);
+
+    let mut compiled = computer;
+    let actual = compiled.compile().run(&mut compiled, step_limit);
+
+    assert_eq!(actual, expected);
+    assert_eq!(compiled.io(), interpreted.io());
+    assert_eq!(compiled.sent(), interpreted.sent());
+    assert_eq!(compiled.history_len(), interpreted.history_len());
+    assert_eq!(compiled.trace(), interpreted.trace());
+    compiled
+}
+
+#[test]
+fn prime_search() {
+    for a in [0, 1] {
+        let mut computer = Computer::with_io(parse(PRIME_SEARCH), Queue::default());
+        computer.set_register(register('a'), a);
+        let computer = check(computer, None);
+        assert!(computer.is_halted());
+    }
+
+    let mut computer = Computer::with_io(parse(PRIME_SEARCH), Queue::default());
+    computer.set_register(register('a'), 1);
+    assert_eq!(check(computer, None).register(register('h')), 9);
+}
+
+#[test]
+fn optimized() {
+    for a in [0, 1] {
+        let mut computer = Computer::with_io(optimize(&parse(PRIME_SEARCH)), Queue::default());
+        computer.set_register(register('a'), a);
+        check(computer, None);
+    }
+}
+
+#[test]
+fn step_limits() {
+    for limit in [0, 1, 2, 17, 1000] {
+        check(
+            Computer::with_io(parse(PRIME_SEARCH), Queue::default()),
+            Some(limit),
+        );
+    }
+}
+
+#[test]
+fn io() {
+    let mut computer = Computer::with_io(parse(DUET), Queue::default());
+    computer.set_register(register('p'), 7);
+    computer.io_mut().input.extend([4, 5]);
+    let computer = check(computer, None);
+    assert_eq!(computer.io().output, [1, 2, 7]);
+
+    let mut computer = Computer::with_io(parse(SOUND), Queue::default());
+    computer.set_semantics(Semantics::Sound);
+    check(computer, Some(100));
+}
+
+#[test]
+fn history() {
+    let mut computer = Computer::with_io(parse(PRIME_SEARCH), Queue::default());
+    computer.enable_history();
+    let mut computer = check(computer, Some(500));
+    while computer.step_back().expect("Nothing was received.") {}
+    assert_eq!(computer.instruction_pointer(), 0);
+    assert!(computer.registers().iter().all(|(_, value)| value == 0));
+}
+
+#[test]
+fn traces() {
+    let mut computer = Computer::with_io(parse(DUET), Queue::new([4, 5]));
+    computer.set_register(register('p'), 7);
+    computer.enable_tracing();
+    let computer = check(computer, None);
+    let trace: Vec<String> = computer.trace().iter().map(ToString::to_string).collect();
+    assert_eq!(
+        trace,
+        [
+            "0 0: snd 1 | >1",
+            "1 1: snd 2 | >2",
+            "2 2: snd p | >7",
+            "3 3: rcv a | a=4 <4",
+            "4 4: rcv b | b=5 <5",
+        ]
+    );
+
+    let mut computer = Computer::with_io(parse(PRIME_SEARCH), Queue::default());
+    computer.enable_tracing();
+    let computer = check(computer, Some(2000));
+    assert_eq!(computer.trace().len(), 2000);
+}
+
+#[test]
+fn errors() {
+    for program in [
+        "set a 1\nmod a 0",
+        "set a 1\nset b 0\nmod a b",
+        "jnz 1 -1",
+        "set a 1\njgz a -5",
+        "set a 9223372036854775807\nadd a 1",
+        "set a 2\nmul a a\njnz 1 -1",
+        "set a -9223372036854775808\nmod a -1",
+        "snd 1\njnz 1 9223372036854775807",
+        "rcv a",
+    ] {
+        let computer = check(Computer::with_io(parse(program), Queue::default()), None);
+        assert!(!computer.is_halted(), "{}", program);
+    }
+}
+
+#[test]
+fn modes() {
+    let program = "set a -7\nset b 3\nmod a b\nset c 9223372036854775807\nmul c c";
+    for arithmetic in [
+        ArithmeticMode::Wrapping,
+        ArithmeticMode::Checked,
+        ArithmeticMode::Saturating,
+    ] {
+        for modulo in [ModuloMode::Truncated, ModuloMode::Euclidean] {
+            let mut computer = Computer::with_io(parse(program), Queue::default());
+            computer.set_arithmetic_mode(arithmetic);
+            computer.set_modulo_mode(modulo);
+            check(computer, None);
+        }
+    }
+
+    // Compiled for the default modes, and then run in another one.
+    let mut computer = Computer::with_io(parse(program), Queue::default());
+    let compiled = computer.compile();
+    computer.set_arithmetic_mode(ArithmeticMode::Wrapping);
+    let halt = compiled.run(&mut computer, None);
+    assert_eq!(halt.error, None);
+    assert_eq!(computer.register(register('c')), 1);
+}
+
+#[test]
+fn random_programs() {
+    let mut random = Random(2017);
+    for _ in 0..2000 {
+        let len = 1 + random.below(12) as usize;
+        let instructions: Vec<Instruction> = (0..len).map(|_| random.instruction()).collect();
+        let mut computer = Computer::with_io(instructions, Queue::default());
+        for name in ['a', 'b', 'c', 'd'] {
+            computer.set_register(register(name), random.below(21) as Value - 10);
+        }
+        check(computer, Some(1000));
+    }
+}
@@ -0,0 +1,44 @@
+use advent_of_code_2017::intcode::assembler::{assemble, line_numbers};
+use advent_of_code_2017::intcode::coverage::Coverage;
+use advent_of_code_2017::intcode::io::Queue;
+use advent_of_code_2017::intcode::Computer;
+
+mod common;
+
+use common::register;
+
+const SOURCE: &str = "# b is 1, or 2 when a is not positive.
+set b 2
+jgz a 2
+
+set b 3
+sub b 1
+";
+
+#[test]
+fn lcov() {
+    let instructions = assemble(SOURCE).unwrap();
+    let mut computer = Computer::with_io(instructions.clone(), Queue::default());
+    computer.set_register(register('a'), 5);
+    let mut coverage = Coverage::new(&instructions);
+    coverage.add(&computer.profile());
+
+    // The jump is always taken, so `set b 3` never runs.
+    assert_eq!(
+        coverage.lcov("example.asm", &line_numbers(SOURCE)),
+        "TN:
+SF:example.asm
+BRDA:3,0,0,1
+BRDA:3,0,1,0
+BRF:2
+BRH:1
+DA:2,1
+DA:3,1
+DA:5,0
+DA:6,1
+LF:4
+LH:3
+end_of_record
+"
+    );
+}
@@ -0,0 +1,87 @@
+use advent_of_code_2017::intcode::debugger::{Debugger, Event};
+use advent_of_code_2017::intcode::io::Queue;
+use advent_of_code_2017::intcode::optimizer::optimize;
+use advent_of_code_2017::intcode::{Computer, Instruction};
+
+mod common;
+
+use common::{parse, register};
+
+const MULTIPLY: &str = "set b 3
+set c 4
+add a b
+sub c 1
+jnz c -2";
+
+fn debugger(instructions: Vec<Instruction>) -> Debugger<Queue> {
+    Debugger::new(Computer::with_io(instructions, Queue::default()))
+}
+
+#[test]
+fn watchpoints_see_superinstructions() {
+    let instructions = optimize(&parse(MULTIPLY));
+    assert!(matches!(instructions[2], Instruction::Fused(_)));
+
+    let mut debugger = debugger(instructions);
+    debugger.add_watchpoint(register('a'));
+    assert_eq!(
+        debugger.resume(),
+        Event::Watchpoint {
+            register: register('a'),
+            old: 0,
+            new: 12,
+        }
+    );
+    assert_eq!(debugger.resume(), Event::Halted);
+
+    // The counter is written as well.
+    let mut debugger = self::debugger(optimize(&parse(MULTIPLY)));
+    debugger.add_watchpoint(register('c'));
+    assert_eq!(
+        debugger.resume(),
+        Event::Watchpoint {
+            register: register('c'),
+            old: 0,
+            new: 4,
+        }
+    );
+    assert_eq!(
+        debugger.resume(),
+        Event::Watchpoint {
+            register: register('c'),
+            old: 4,
+            new: 0,
+        }
+    );
+    assert_eq!(debugger.resume(), Event::Halted);
+}
+
+#[test]
+fn breakpoints_after_watchpoints() {
+    let mut debugger = debugger(parse(MULTIPLY));
+    debugger.add_watchpoint(register('b'));
+    debugger.add_breakpoint(1);
+
+    assert_eq!(
+        debugger.resume(),
+        Event::Watchpoint {
+            register: register('b'),
+            old: 0,
+            new: 3,
+        }
+    );
+    assert_eq!(debugger.resume(), Event::Breakpoint(1));
+    assert_eq!(debugger.computer().instruction_pointer(), 1);
+    // And then it carries on past it.
+    assert_eq!(debugger.resume(), Event::Halted);
+    assert_eq!(debugger.computer().register(register('a')), 12);
+
+    // Stepping away from the breakpoint forgets it.
+    let mut debugger = self::debugger(parse(MULTIPLY));
+    debugger.add_watchpoint(register('b'));
+    debugger.add_breakpoint(1);
+    debugger.add_breakpoint(3);
+    debugger.resume();
+    assert_eq!(debugger.step(), Event::Stepped);
+    assert_eq!(debugger.resume(), Event::Breakpoint(3));
+}
@@ -0,0 +1,167 @@
+use advent_of_code_2017::intcode::decompiler::Decompiler;
+
+mod common;
+
+use common::{parse, register, PRIME_SEARCH, SOUND};
+
+fn decompile(program: &str) -> String {
+    Decompiler::new(&parse(program)).decompile()
+}
+
+#[test]
+fn day18() {
+    assert_eq!(
+        decompile(SOUND),
+        "\
+a = 1
+a += 2
+a *= a
+a %= 5
+send(a)
+a = 0
+do {
+    a = receive()
+L7:
+} while a > 0
+a = 1
+if a > 0 { goto L7 }
+"
+    );
+}
+
+#[test]
+fn day23() {
+    let decompiler = Decompiler::new(&parse(PRIME_SEARCH))
+        .name(register('b'), "n")
+        .name(register('h'), "composites");
+    assert_eq!(
+        decompiler.decompile(),
+        "\
+n = 93
+c = n
+if a != 0 {
+    n *= 2
+    n += 100
+    c = n
+    c += 170
+}
+loop {
+    f = 1
+    d = 2
+    do {
+        e = 2
+        do {
+            g = d
+            g *= e
+            g -= n
+            if g == 0 {
+                f = 0
+            }
+            e += 1
+            g = e
+            g -= n
+        } while g != 0
+        d += 1
+        g = d
+        g -= n
+    } while g != 0
+    if f == 0 {
+        composites += 1
+    }
+    g = n
+    g -= c
+    if g == 0 {
+        halt
+    }
+    n += 17
+}
+"
+    );
+}
+
+#[test]
+fn if_else() {
+    let program = "jgz a 3\nset b 1\njnz 1 2\nset b 2\nsnd b";
+    assert_eq!(
+        decompile(program),
+        "\
+if a <= 0 {
+    b = 1
+} else {
+    b = 2
+}
+send(b)
+"
+    );
+
+    // Jumping to the end of the \"then\" part, from outside.
+    assert_eq!(
+        decompile(&format!("{}\njgz e -3", program)),
+        "\
+if a <= 0 {
+    b = 1
+L2:
+} else {
+    b = 2
+}
+send(b)
+if e > 0 { goto L2 }
+"
+    );
+}
+
+#[test]
+fn while_loops() {
+    assert_eq!(
+        decompile("set a 3\njnz a 2\njnz 1 4\nsnd a\nsub a 1\njnz 1 -4\nsnd 0"),
+        "\
+a = 3
+while a != 0 {
+    send(a)
+    a -= 1
+}
+send(0)
+"
+    );
+
+    // Leaving the loop also leaves the program.
+    assert_eq!(
+        decompile("jgz a 3\nadd a 1\njnz 1 -2"),
+        "\
+while a <= 0 {
+    a += 1
+}
+"
+    );
+
+    // Jumping to the jump out of the loop.
+    assert_eq!(
+        decompile("jnz a 2\njnz 1 3\nsub a 1\njnz 1 -3\njgz e -3"),
+        "\
+while a != 0 {
+    a -= 1
+}
+L1:
+if e > 0 { goto L1 }
+"
+    );
+}
+
+#[test]
+fn gotos() {
+    // Jumping back into the "else" part makes a loop with two entries, which
+    // is not structured.
+    assert_eq!(
+        decompile("jgz a 3\nset b 1\njnz 1 2\nadd b 2\nadd b 1\njgz b -2"),
+        "\
+if a <= 0 {
+    b = 1
+} else {
+L3:
+    b += 2
+}
+b += 1
+if b > 0 { goto L3 }
+"
+    );
+}
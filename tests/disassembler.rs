@@ -0,0 +1,58 @@
+use advent_of_code_2017::intcode::assembler::assemble;
+use advent_of_code_2017::intcode::disassembler::disassemble;
+
+mod common;
+
+use common::{parse, DUET, PRIME_SEARCH, SOUND};
+
+const JUMPS: &str = "set a 3
+sub a 1
+jnz a -1
+jgz a 3
+jgz 1 10
+jnz 1 -9";
+
+#[test]
+fn labels() {
+    assert_eq!(
+        disassemble(&parse(JUMPS)),
+        "   0: set a 3
+      L1:
+   1: sub a 1
+   2: jnz a -1  # -> L1
+   3: jgz a 3   # -> L6
+   4: jgz 1 10  # -> 14 (exit)
+   5: jnz 1 -9  # -> -4 (invalid)
+      L6:
+"
+    );
+}
+
+#[test]
+fn without_jumps() {
+    assert_eq!(
+        disassemble(&parse("snd 1\nrcv a")),
+        "   0: snd 1\n   1: rcv a\n"
+    );
+    assert_eq!(disassemble(&[]), "");
+    // A jump on a register can land anywhere, so it gets no label.
+    assert_eq!(disassemble(&parse("jnz 1 a")), "   0: jnz 1 a\n");
+}
+
+#[test]
+fn round_trip() {
+    for source in [JUMPS, DUET, SOUND, PRIME_SEARCH] {
+        let program = parse(source);
+        let listing = disassemble(&program);
+        assert_eq!(assemble(&listing), Ok(program), "{}", listing);
+    }
+
+    // Addresses are checked.
+    let listing = disassemble(&parse(JUMPS)).replace("   3:", "   4:");
+    let diagnostics = assemble(&listing).unwrap_err();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "5:4: Wrong address 4, expected 3"
+    );
+}
@@ -0,0 +1,99 @@
+use advent_of_code_2017::intcode::io::{Closures, IoError, Queue};
+use advent_of_code_2017::intcode::{Computer, ExecError, Status};
+
+mod common;
+
+use common::{parse, register, PRIME_SEARCH};
+
+#[test]
+fn step_back_over_io() {
+    let mut computer = Computer::with_io(parse("snd 5\nrcv a\nadd a 1"), Queue::new([7, 8]));
+    computer.enable_history();
+    computer.run();
+    assert_eq!(computer.register(register('a')), 8);
+    assert_eq!(computer.io().output, [5]);
+
+    assert_eq!(computer.step_back(), Ok(true));
+    assert_eq!(computer.register(register('a')), 7);
+    // The received value goes back in front of the input.
+    assert_eq!(computer.step_back(), Ok(true));
+    assert_eq!(computer.instruction_pointer(), 1);
+    assert_eq!(computer.register(register('a')), 0);
+    assert_eq!(computer.io().input, [7, 8]);
+    // What was sent stays sent, but is no longer counted.
+    assert_eq!(computer.step_back(), Ok(true));
+    assert_eq!(computer.sent(), 0);
+    assert_eq!(computer.io().output, [5]);
+    assert_eq!(computer.step_back(), Ok(false));
+
+    // Running again receives the same value.
+    assert_eq!(computer.try_step(), Ok(Status::Running));
+    assert_eq!(computer.try_step(), Ok(Status::Running));
+    assert_eq!(computer.register(register('a')), 7);
+}
+
+#[test]
+fn step_back_after_an_error() {
+    let mut computer = Computer::with_io(parse("set a 3\nmod a 0"), Queue::default());
+    computer.enable_history();
+    assert_eq!(
+        computer.run_until_halt(None).error,
+        Some(ExecError::DivisionByZero)
+    );
+    // The failed step changed nothing, so there is nothing to undo for it.
+    assert_eq!(computer.history_len(), 1);
+    assert_eq!(computer.instruction_pointer(), 1);
+    assert_eq!(computer.step_back(), Ok(true));
+    assert_eq!(computer.instruction_pointer(), 0);
+    assert_eq!(computer.register(register('a')), 0);
+}
+
+#[test]
+fn bounded_history() {
+    let mut computer = Computer::with_io(parse(PRIME_SEARCH), Queue::default());
+    computer.enable_bounded_history(10);
+    computer.run_until_halt(Some(1000));
+    assert_eq!(computer.history_len(), 10);
+    let registers = *computer.registers();
+
+    for _ in 0..10 {
+        assert_eq!(computer.step_back(), Ok(true));
+    }
+    assert_eq!(computer.step_back(), Ok(false));
+    computer.run_until_halt(Some(10));
+    assert_eq!(computer.registers(), &registers);
+
+    // Lowering the limit forgets the oldest steps.
+    computer.enable_bounded_history(4);
+    assert_eq!(computer.history_len(), 4);
+    assert_eq!(computer.history_limit(), Some(Some(4)));
+    computer.disable_history();
+    assert_eq!(computer.history_limit(), None);
+}
+
+#[test]
+fn rewinding_is_all_or_nothing() {
+    let mut computer = Computer::with_io(parse("rcv a\nset b 1\nset c 2"), Queue::new([4]));
+    computer.enable_history();
+    computer.run();
+    assert_eq!(computer.rewind_to_write(register('d')), Ok(None));
+    assert_eq!(computer.history_len(), 3);
+    assert_eq!(computer.rewind_to_write(register('a')), Ok(Some(0)));
+    assert_eq!(computer.io().input, [4]);
+    assert_eq!(computer.register(register('b')), 0);
+
+    // Without a way to put the received value back, nothing is undone.
+    let mut values = vec![4];
+    let io = Closures::new(|_| (), move || values.pop());
+    let mut computer = Computer::with_io(parse("rcv a\nset b 1\nset c 2"), io);
+    computer.enable_history();
+    computer.run();
+    assert_eq!(
+        computer.rewind_to_write(register('a')),
+        Err(IoError::Unsupported)
+    );
+    assert_eq!(computer.history_len(), 3);
+    assert_eq!(computer.instruction_pointer(), 3);
+    assert_eq!(computer.register(register('b')), 1);
+    assert_eq!(computer.register(register('c')), 2);
+}
@@ -0,0 +1,438 @@
+use advent_of_code_2017::intcode::assembler::assemble_with;
+use advent_of_code_2017::intcode::dialect::{Dialect, DIV, EXTRAS, JLZ, OUT};
+use advent_of_code_2017::intcode::io::{NoIo, Queue};
+use advent_of_code_2017::intcode::network::{Network, Topology};
+use advent_of_code_2017::intcode::optimizer::optimize;
+use advent_of_code_2017::intcode::{
+    Computer, ExecError, Instruction, Parameter, ParseError, Register, Semantics, Status, Value,
+};
+
+mod common;
+
+use common::{parse, register, Random, DUET, PRIME_SEARCH, SOUND};
+
+/// The `Computer` of day 18 from before the `intcode` module existed, kept
+/// as an independent model of the instructions both understand.
+mod reference {
+    use std::collections::HashMap;
+
+    pub type Value = i64;
+
+    #[derive(Debug, Clone, Copy)]
+    pub enum Parameter {
+        Value(Value),
+        Register(char),
+    }
+
+    fn parameter(s: &str) -> Parameter {
+        s.parse().map_or_else(
+            |_| Parameter::Register(s.chars().next().unwrap()),
+            Parameter::Value,
+        )
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    pub enum Instruction {
+        Sound(Parameter),
+        Set(char, Parameter),
+        Add(char, Parameter),
+        Multiply(char, Parameter),
+        Modulo(char, Parameter),
+        Recover(Parameter),
+        JumpGreaterThanZero(char, Parameter),
+    }
+
+    pub fn instruction(s: &str) -> Instruction {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let register = || words[1].chars().next().unwrap();
+        match words[0] {
+            "snd" => Instruction::Sound(parameter(words[1])),
+            "set" => Instruction::Set(register(), parameter(words[2])),
+            "add" => Instruction::Add(register(), parameter(words[2])),
+            "mul" => Instruction::Multiply(register(), parameter(words[2])),
+            "mod" => Instruction::Modulo(register(), parameter(words[2])),
+            "rcv" => Instruction::Recover(parameter(words[1])),
+            "jgz" => Instruction::JumpGreaterThanZero(register(), parameter(words[2])),
+            op => panic!("Unknown operation: {}", op),
+        }
+    }
+
+    #[derive(Debug, Default)]
+    pub struct Computer {
+        pub registers: HashMap<char, Value>,
+        pub instructions: Vec<Instruction>,
+        pub last_played: Value,
+        pub instruction_pointer: usize,
+    }
+
+    impl Computer {
+        fn get_value(&self, parameter: Parameter) -> Value {
+            match parameter {
+                Parameter::Value(v) => v,
+                Parameter::Register(r) => self.registers.get(&r).copied().unwrap_or_default(),
+            }
+        }
+
+        /// The frequency recovered, if any.
+        pub fn step(&mut self) -> Option<Value> {
+            match self.instructions[self.instruction_pointer] {
+                Instruction::Sound(p) => self.last_played = self.get_value(p),
+                Instruction::Set(r, p) => {
+                    self.registers.insert(r, self.get_value(p));
+                }
+                Instruction::Add(r, p) => {
+                    *self.registers.entry(r).or_default() += self.get_value(p)
+                }
+                Instruction::Multiply(r, p) => {
+                    *self.registers.entry(r).or_default() *= self.get_value(p)
+                }
+                Instruction::Modulo(r, p) => {
+                    *self.registers.entry(r).or_default() %= self.get_value(p)
+                }
+                Instruction::Recover(p) => {
+                    self.instruction_pointer += 1;
+                    return Some(self.last_played).filter(|_| self.get_value(p) != 0);
+                }
+                Instruction::JumpGreaterThanZero(r, p) => {
+                    if *self.registers.entry(r).or_default() > 0 {
+                        self.instruction_pointer =
+                            (self.instruction_pointer as Value + self.get_value(p)) as usize;
+                        return None;
+                    }
+                }
+            }
+            self.instruction_pointer += 1;
+            None
+        }
+    }
+}
+
+impl Random {
+    /// Any value, with a bias towards the extremes.
+    fn value(&mut self) -> Value {
+        match self.below(4) {
+            0 => Value::MIN + self.below(3) as Value,
+            1 => Value::MAX - self.below(3) as Value,
+            _ => self.below(2001) as Value - 1000,
+        }
+    }
+
+    /// Any register, any value.
+    fn any_parameter(&mut self) -> Parameter {
+        match self.below(2) {
+            0 => Parameter::Register(register((b'a' + self.below(26) as u8) as char)),
+            _ => Parameter::Value(self.value()),
+        }
+    }
+
+    /// Anything the permissive dialect with every extra opcode accepts.
+    fn any_instruction(&mut self) -> Instruction {
+        let r = register((b'a' + self.below(26) as u8) as char);
+        match self.below(13) {
+            0 => Instruction::Send(self.any_parameter()),
+            1 => Instruction::Set(r, self.any_parameter()),
+            2 => Instruction::Add(r, self.any_parameter()),
+            3 => Instruction::Sub(r, self.any_parameter()),
+            4 => Instruction::Multiply(r, self.any_parameter()),
+            5 => Instruction::Modulo(r, self.any_parameter()),
+            6 => Instruction::Receive(r),
+            7 => Instruction::JumpGreaterThanZero(self.any_parameter(), self.any_parameter()),
+            8 => Instruction::JumpIfNotZero(self.any_parameter(), self.any_parameter()),
+            9 => Instruction::Extended(&DIV, Parameter::Register(r), self.any_parameter()),
+            10 => Instruction::Extended(&JLZ, self.any_parameter(), self.any_parameter()),
+            11 => Instruction::Extended(&OUT, self.any_parameter(), Parameter::Value(0)),
+            _ => self.instruction(),
+        }
+    }
+
+    /// A line of day 18, in the subset both computers understand.
+    fn duet_line(&mut self) -> String {
+        let r = self.register();
+        match self.below(7) {
+            0 => format!("snd {}", self.parameter()),
+            1 => format!("set {} {}", r, self.parameter()),
+            2 => format!("add {} {}", r, self.parameter()),
+            3 => format!("mul {} {}", r, self.parameter()),
+            4 => format!("mod {} {}", r, self.parameter()),
+            5 => format!("rcv {}", r),
+            _ => format!("jgz {} {}", r, self.parameter()),
+        }
+    }
+}
+
+fn extended() -> Dialect {
+    Dialect::permissive().with(&DIV).with(&JLZ).with(&OUT)
+}
+
+#[test]
+fn print_parse_round_trip() {
+    let dialect = extended();
+    let mut random = Random(18);
+    for _ in 0..200 {
+        let instructions: Vec<Instruction> = (0..1 + random.below(30))
+            .map(|_| random.any_instruction())
+            .collect();
+
+        for instruction in &instructions {
+            let line = instruction.to_string();
+            assert_eq!(dialect.parse(&line).as_ref(), Ok(instruction), "{}", line);
+            let spaced = format!("  {}\t", line.replace(' ', "  \t "));
+            assert_eq!(dialect.parse(&spaced).as_ref(), Ok(instruction), "{}", line);
+        }
+
+        let source: Vec<String> = instructions.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            assemble_with(&source.join("\n"), &dialect),
+            Ok(instructions)
+        );
+    }
+}
+
+#[test]
+fn matches_the_day18_computer() {
+    let mut random = Random(2018);
+    for _ in 0..1000 {
+        let lines: Vec<String> = (0..1 + random.below(12))
+            .map(|_| random.duet_line())
+            .collect();
+
+        let mut model = reference::Computer {
+            instructions: lines
+                .iter()
+                .map(|line| reference::instruction(line))
+                .collect(),
+            ..Default::default()
+        };
+        let instructions = lines.iter().map(|line| line.parse().unwrap()).collect();
+        let mut computer = Computer::with_io(instructions, Queue::default());
+        computer.set_semantics(Semantics::Sound);
+        for name in ['a', 'b', 'c', 'd'] {
+            let value = random.below(21) as Value - 10;
+            computer.set_register(register(name), value);
+            model.registers.insert(name, value);
+        }
+
+        for _ in 0..500 {
+            if computer.is_halted() {
+                assert!(model.instruction_pointer >= lines.len());
+                break;
+            }
+            // The model panics (or worse) on overflow, division by zero and
+            // negative jumps, all of which the computer reports instead.
+            if computer.try_step().is_err() {
+                break;
+            }
+            let recovered = model.step();
+
+            let snapshot = computer.snapshot().unwrap();
+            assert_eq!(
+                snapshot.instruction_pointer, model.instruction_pointer,
+                "{:?}",
+                lines
+            );
+            assert_eq!(snapshot.last_played, model.last_played, "{:?}", lines);
+            if recovered.is_some() {
+                assert_eq!(snapshot.recovered, recovered, "{:?}", lines);
+            }
+            for (name, &value) in &model.registers {
+                assert_eq!(snapshot.registers[register(*name)], value, "{:?}", lines);
+            }
+        }
+    }
+}
+
+#[test]
+fn pc_moves_by_one_or_by_the_offset() {
+    let mut random = Random(23);
+    for _ in 0..1000 {
+        let instructions: Vec<Instruction> = (0..1 + random.below(12))
+            .map(|_| match random.below(6) {
+                0 => Instruction::Receive(random.register()),
+                1 => Instruction::Extended(&JLZ, random.parameter(), random.parameter()),
+                _ => random.instruction(),
+            })
+            .collect();
+        let mut computer = Computer::with_io(instructions, Queue::new([3, -1, 0, 7]));
+        for name in ['a', 'b', 'c', 'd'] {
+            computer.set_register(register(name), random.below(21) as Value - 10);
+        }
+
+        for _ in 0..300 {
+            let pc = computer.instruction_pointer();
+            let registers = *computer.registers();
+            let instruction = match computer.current_instruction() {
+                Some(instruction) => instruction,
+                None => break,
+            };
+            let value = |p: Parameter| match p {
+                Parameter::Value(v) => v,
+                Parameter::Register(r) => registers[r],
+            };
+            let jump = instruction
+                .jump()
+                .map(|(x, offset)| (value(x), value(offset)));
+
+            match computer.try_step() {
+                Ok(Status::Running) => (),
+                Ok(_) => break,
+                Err(_) => {
+                    // A failed step changes nothing.
+                    assert_eq!(computer.instruction_pointer(), pc);
+                    assert_eq!(computer.registers(), &registers);
+                    break;
+                }
+            }
+
+            match jump {
+                Some((x, offset)) if instruction.is_taken(x) == Some(true) => {
+                    assert_eq!(
+                        computer.instruction_pointer() as Value,
+                        pc as Value + offset
+                    )
+                }
+                _ => assert_eq!(computer.instruction_pointer(), pc + 1, "{}", instruction),
+            }
+            for (r, before) in registers.iter() {
+                if Some(r) != instruction.destination() {
+                    assert_eq!(computer.register(r), before, "{}", instruction);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn day18_part1_example() {
+    let instructions = assemble_with(SOUND, &Dialect::duet()).unwrap();
+    let mut computer = Computer::with_io(instructions, NoIo);
+    computer.set_semantics(Semantics::Sound);
+    assert_eq!(computer.recover(), Some(4));
+}
+
+#[test]
+fn day18_part2_example() {
+    let instructions = assemble_with(DUET, &Dialect::duet()).unwrap();
+    let mut network = Network::with_program(&instructions, Topology::ring(2));
+    network.run();
+    assert_eq!(network.messages(1, 0), 3);
+    assert_eq!(network.messages(0, 1), 3);
+}
+
+/// Day 23 has no example program, so this is the shape of its puzzle inputs,
+/// with smaller bounds.
+#[test]
+fn day23_prime_search() {
+    let instructions = assemble_with(PRIME_SEARCH, &Dialect::coprocessor()).unwrap();
+
+    // Part 1 executes `mul` once for every pair of `d` and `e` in 2..b.
+    let mut computer = Computer::with_io(instructions.clone(), NoIo);
+    assert_eq!(computer.debug_mode(), 91 * 91);
+
+    // Part 2 counts the composite numbers among 286, 303, ..., 456.
+    for program in [instructions.clone(), optimize(&instructions)] {
+        let mut computer = Computer::with_io(program, NoIo);
+        computer.set_register(register('a'), 1);
+        computer.run();
+        assert_eq!(computer.register(register('h')), 9);
+    }
+}
+
+#[test]
+fn parse_agrees_with_the_dialects() {
+    assert_eq!(
+        parse(PRIME_SEARCH),
+        assemble_with(PRIME_SEARCH, &Dialect::coprocessor()).unwrap()
+    );
+    assert_eq!(
+        parse(SOUND),
+        assemble_with(SOUND, &Dialect::duet()).unwrap()
+    );
+    assert!(assemble_with(SOUND, &Dialect::coprocessor()).is_err());
+    assert!(assemble_with(PRIME_SEARCH, &Dialect::duet()).is_err());
+}
+
+#[test]
+fn strict_dialect() {
+    let (strict, permissive) = (Dialect::strict(), Dialect::permissive());
+    assert_ne!(strict, permissive);
+    for line in ["snd a", "jgz a -1", "jnz 1 2", "mod a 3", "sub b 4"] {
+        assert!(strict.parse(line).is_ok(), "{}", line);
+        assert_eq!(strict.parse(line), permissive.parse(line), "{}", line);
+    }
+
+    assert_eq!(
+        strict.parse("div a 3"),
+        Err(ParseError::UnknownOperation("div".to_string()))
+    );
+    assert_eq!(
+        permissive.parse("div a 3"),
+        Ok(Instruction::Extended(
+            &DIV,
+            Parameter::Register(register('a')),
+            Parameter::Value(3)
+        ))
+    );
+    for opcode in EXTRAS {
+        assert!(!strict.allows(opcode.mnemonic), "{}", opcode.mnemonic);
+        assert!(permissive.allows(opcode.mnemonic), "{}", opcode.mnemonic);
+    }
+    assert_eq!("strict".parse(), Ok(strict));
+}
+
+#[test]
+fn parse_errors() {
+    for (line, error) in [
+        ("", ParseError::MissingOperation),
+        ("nop a", ParseError::UnknownOperation("nop".to_string())),
+        ("set", ParseError::MissingParameter(1)),
+        ("set a", ParseError::MissingParameter(2)),
+        (
+            "set 12abc 5",
+            ParseError::InvalidRegister("12abc".to_string()),
+        ),
+        ("set ab 5", ParseError::InvalidRegister("ab".to_string())),
+        ("add a 5b", ParseError::InvalidParameter("5b".to_string())),
+        (
+            "jnz 1 2 3",
+            ParseError::UnexpectedParameter("3".to_string()),
+        ),
+        ("div 3 a", ParseError::UnknownOperation("div".to_string())),
+    ] {
+        assert_eq!(line.parse::<Instruction>(), Err(error), "{}", line);
+    }
+
+    assert_eq!(
+        "ab".parse::<Register>(),
+        Err(ParseError::InvalidRegister("ab".to_string()))
+    );
+    assert_eq!(
+        Register::try_from('A'),
+        Err(ParseError::InvalidRegister("A".to_string()))
+    );
+    assert_eq!(
+        " 5b ".parse::<Parameter>(),
+        Err(ParseError::InvalidParameter("5b".to_string()))
+    );
+    assert_eq!(
+        Dialect::duet().parse("jnz a 2"),
+        Err(ParseError::NotAllowed("jnz".to_string()))
+    );
+    assert_eq!(
+        extended().parse("div 3 a"),
+        Err(ParseError::InvalidRegister("3".to_string()))
+    );
+}
+
+#[test]
+fn invalid_operands_are_errors() {
+    let instructions = vec![Instruction::Extended(
+        &DIV,
+        Parameter::Value(3),
+        Parameter::Value(1),
+    )];
+    let mut computer = Computer::with_io(instructions, NoIo);
+    assert_eq!(
+        computer.run_until_halt(None).error,
+        Some(ExecError::InvalidOperand)
+    );
+}
@@ -0,0 +1,114 @@
+use advent_of_code_2017::intcode::interval::{
+    summarize_loops, CompositeCount, Interval, IntervalAnalysis, State,
+};
+use advent_of_code_2017::intcode::optimizer::optimize;
+use advent_of_code_2017::intcode::Value;
+use std::collections::BTreeSet;
+
+mod common;
+
+use common::{parse, register, PRIME_SEARCH};
+
+fn interval(min: Value, max: Value) -> Interval {
+    Interval::new(min, max).expect("The test intervals are not empty.")
+}
+
+#[test]
+fn unreachable() {
+    let analysis = IntervalAnalysis::new(&parse(PRIME_SEARCH), State::default());
+    assert_eq!(analysis.unreachable(), [4, 5, 6, 7, 30, 31]);
+    assert!(analysis.non_terminating().is_empty());
+
+    let mut initial = State::default();
+    initial[register('a')] = interval(0, 1);
+    let analysis = IntervalAnalysis::new(&parse(PRIME_SEARCH), initial);
+    assert_eq!(analysis.unreachable(), Vec::<usize>::new());
+}
+
+#[test]
+fn non_terminating() {
+    let analysis = IntervalAnalysis::new(&parse("set a 1\njgz a 0"), State::default());
+    assert_eq!(analysis.non_terminating(), BTreeSet::from([0, 1]));
+    assert!(analysis.listing().contains("(never halts)"));
+
+    // Only one way out of the first instruction loops forever.
+    let mut initial = State::default();
+    initial[register('a')] = interval(0, 1);
+    let program = parse("jgz a 2\njnz 1 0\nset b 1");
+    let analysis = IntervalAnalysis::new(&program, initial);
+    assert_eq!(analysis.non_terminating(), BTreeSet::from([1]));
+}
+
+#[test]
+fn widening() {
+    // Counts up forever, until it overflows.
+    let program = parse("set a 3\nadd a 2\njnz 1 -1");
+    let analysis = IntervalAnalysis::new(&program, State::default());
+    let state = analysis.state(2).unwrap();
+    assert_eq!(state[register('a')], interval(5, Value::MAX));
+    assert_eq!(state[register('a')].to_string(), "[5, +inf)");
+}
+
+#[test]
+fn infinities_stay_infinite() {
+    let mut initial = State::default();
+    initial[register('a')] = interval(5, Value::MAX);
+    initial[register('b')] = interval(Value::MIN, 0);
+    let program = parse("sub a 93\nadd b 7\nmul a -1\nset c a\nmul c c");
+    let analysis = IntervalAnalysis::new(&program, initial);
+
+    let state = analysis.state(1).unwrap();
+    assert_eq!(state[register('a')].to_string(), "[-88, +inf)");
+    let state = analysis.state(2).unwrap();
+    assert_eq!(state[register('b')].to_string(), "(-inf, 7]");
+    let state = analysis.state(4).unwrap();
+    assert_eq!(state[register('a')].to_string(), "(-inf, 88]");
+    assert_eq!(
+        analysis.listing().lines().nth(4),
+        Some("   4: mul c c               a = (-inf, 88], b = (-inf, 7], c = (-inf, 88]")
+    );
+
+    assert_eq!(Interval::constant(Value::MAX).to_string(), "+inf");
+    assert_eq!(Interval::TOP.to_string(), "(-inf, +inf)");
+}
+
+#[test]
+fn known_zeros_are_listed() {
+    // `b` is always 0, `a` only some of the time.
+    let analysis = IntervalAnalysis::new(&parse("add a 1\nset a 0\nadd b a"), State::default());
+    assert_eq!(
+        analysis.listing(),
+        "   0: add a 1               a = 0\n   \
+            1: set a 0               a = 1\n   \
+            2: add b a               a = 0\n"
+    );
+}
+
+#[test]
+fn composite_counts() {
+    let count = CompositeCount {
+        counter: register('h'),
+        candidate: register('b'),
+        step: 17,
+        end: register('c'),
+    };
+    assert_eq!(
+        count.to_string(),
+        "h counts the composite numbers in [b, c] step 17"
+    );
+
+    for program in [parse(PRIME_SEARCH), optimize(&parse(PRIME_SEARCH))] {
+        let summaries = summarize_loops(&program);
+        assert_eq!(summaries[0].header, 8);
+        assert_eq!(summaries[0].composite_counts, [count]);
+        assert!(summaries[1..]
+            .iter()
+            .all(|summary| summary.composite_counts.is_empty()));
+    }
+
+    // Without resetting the flag, it only counts up to the first composite.
+    let program = PRIME_SEARCH.replace("set f 1", "set f 0");
+    assert!(summarize_loops(&parse(&program))[0]
+        .composite_counts
+        .is_empty());
+}
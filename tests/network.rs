@@ -0,0 +1,87 @@
+use advent_of_code_2017::intcode::network::{Message, Network, Topology};
+use advent_of_code_2017::intcode::scheduler::Termination;
+use std::collections::BTreeMap;
+
+mod common;
+
+use common::{parse, register};
+
+/// Machine 0 sends 1, and every other one adds its index to what it
+/// receives and sends that on.
+const RELAY: &str = "jgz p 3
+snd 1
+jgz 1 4
+rcv a
+add a p
+snd a";
+
+/// Machine 0 sends 5 and 7, and every other one receives two values.
+const ANNOUNCE: &str = "jgz p 4
+snd 5
+snd 7
+jgz 1 3
+rcv a
+rcv b";
+
+#[test]
+fn pipeline() {
+    let topology = Topology::pipeline(3);
+    assert_eq!(topology.len(), 3);
+    assert_eq!(topology.successors(0), [1]);
+    assert_eq!(topology.successors(1), [2]);
+    assert_eq!(topology.successors(2), [] as [usize; 0]);
+
+    let mut network = Network::with_program(&parse(RELAY), topology);
+    let report = network.run();
+    assert_eq!(report.termination, Termination::Halted);
+    assert_eq!(report.sent, [1, 1, 1]);
+    assert_eq!(network.links().collect::<Vec<_>>(), [(0, 1, 1), (1, 2, 1)]);
+    // The last machine keeps what it sent.
+    assert_eq!(network.computers()[2].io().output, [4]);
+}
+
+#[test]
+fn broadcast() {
+    let topology = Topology::broadcast(3);
+    assert_eq!(topology.successors(0), [1, 2]);
+    assert_eq!(topology.successors(1), [0, 2]);
+    assert_eq!(topology.successors(2), [0, 1]);
+
+    let mut network = Network::with_program(&parse(ANNOUNCE), topology);
+    network.enable_tracing();
+    let report = network.run();
+    assert_eq!(report.termination, Termination::Halted);
+
+    for computer in &network.computers()[1..] {
+        assert_eq!(computer.register(register('a')), 5);
+        assert_eq!(computer.register(register('b')), 7);
+    }
+    let message = |to, value| Message { from: 0, to, value };
+    assert_eq!(
+        network.trace(),
+        [message(1, 5), message(2, 5), message(1, 7), message(2, 7)]
+    );
+    assert_eq!(network.messages(0, 1), 2);
+    assert_eq!(network.messages(0, 2), 2);
+    assert_eq!(network.messages(1, 2), 0);
+}
+
+#[test]
+fn adjacency() {
+    let map = BTreeMap::from([(0, vec![2]), (2, vec![0, 1])]);
+    let topology = Topology::adjacency(3, &map).unwrap();
+    assert_eq!(topology.successors(0), [2]);
+    assert_eq!(topology.successors(1), [] as [usize; 0]);
+    assert_eq!(topology.successors(2), [0, 1]);
+
+    for map in [
+        BTreeMap::from([(3, vec![0])]),
+        BTreeMap::from([(0, vec![1, 3])]),
+    ] {
+        assert_eq!(
+            Topology::adjacency(3, &map),
+            Err("No such machine: 3".to_string())
+        );
+    }
+    assert!(Topology::adjacency(0, &BTreeMap::from([(0, vec![])])).is_err());
+}
@@ -0,0 +1,93 @@
+use advent_of_code_2017::intcode::budget::Outcome;
+use advent_of_code_2017::intcode::io::NoIo;
+use advent_of_code_2017::intcode::profiler::Branch;
+use advent_of_code_2017::intcode::Computer;
+use std::collections::BTreeMap;
+
+mod common;
+
+use common::{parse, register};
+
+/// Adds 3, 2 and 1 into `b`, then skips the last instruction.
+const SUM: &str = "set a 3
+set b 0
+add b a
+sub a 1
+jnz a -2
+jgz b 2
+set c 1";
+
+#[test]
+fn counted_loop() {
+    let instructions = parse(SUM);
+    let mut computer = Computer::with_io(instructions.clone(), NoIo);
+    let profile = computer.profile();
+    assert_eq!(computer.register(register('b')), 6);
+
+    assert_eq!(profile.error, None);
+    assert_eq!(profile.steps, 12);
+    assert_eq!(profile.counts, [1, 1, 3, 3, 3, 1, 0]);
+    assert_eq!(
+        profile.opcodes,
+        BTreeMap::from([("add", 3), ("jgz", 1), ("jnz", 3), ("set", 2), ("sub", 3)])
+    );
+    assert_eq!(profile.opcode("set"), 2);
+    assert_eq!(profile.opcode("mul"), 0);
+    assert_eq!(
+        profile.branches,
+        BTreeMap::from([
+            (
+                4,
+                Branch {
+                    taken: 2,
+                    not_taken: 1
+                }
+            ),
+            (
+                5,
+                Branch {
+                    taken: 1,
+                    not_taken: 0
+                }
+            ),
+        ])
+    );
+
+    assert_eq!(
+        profile.listing(&instructions),
+        "           1    0: set a 3
+           1    1: set b 0
+           3    2: add b a
+           3    3: sub a 1
+           3    4: jnz a -2  [taken: 2, not taken: 1]
+           1    5: jgz b 2  [taken: 1, not taken: 0]
+           0    6: set c 1
+"
+    );
+}
+
+#[test]
+fn stops_on_errors() {
+    let mut computer = Computer::with_io(parse("set a 1\nmod a 0\nset b 1"), NoIo);
+    let profile = computer.profile();
+    assert!(profile.error.is_some());
+    // The failed instruction is not counted.
+    assert_eq!(profile.steps, 1);
+    assert_eq!(profile.counts, [1, 0, 0]);
+}
+
+#[test]
+fn budget() {
+    let mut computer = Computer::with_io(parse("set a 1\njgz a 0\nset b 2"), NoIo);
+    let (profile, outcome) = computer.profile_with_budget(1000);
+    assert_eq!(outcome, Outcome::OutOfFuel);
+    // Everything until then is still there.
+    assert_eq!(profile.steps, 1000);
+    assert_eq!(profile.counts, [1, 999, 0]);
+    assert_eq!(profile.branches[&1].taken, 999);
+
+    let mut computer = Computer::with_io(parse(SUM), NoIo);
+    let (profile, outcome) = computer.profile_with_budget(12);
+    assert_eq!(outcome, Outcome::Halted { steps: 12 });
+    assert_eq!(profile.error, None);
+}
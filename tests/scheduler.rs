@@ -0,0 +1,62 @@
+use advent_of_code_2017::intcode::io::{Channel, Queue};
+use advent_of_code_2017::intcode::scheduler::{Scheduler, Termination};
+use advent_of_code_2017::intcode::{Computer, ExecError};
+use std::sync::mpsc::channel;
+
+mod common;
+
+use common::{parse, register, DUET};
+
+fn run(programs: [&str; 2]) -> (Termination, Vec<Option<ExecError>>) {
+    let computers = programs
+        .iter()
+        .map(|program| Computer::with_io(parse(program), Queue::default()))
+        .collect();
+    let report = Scheduler::new(computers).run();
+    (report.termination, report.errors)
+}
+
+#[test]
+fn halted() {
+    assert_eq!(
+        run(["set a 1", "snd 2"]),
+        (Termination::Halted, vec![None, None])
+    );
+}
+
+#[test]
+fn deadlock() {
+    let (to_1, from_0) = channel();
+    let (to_0, from_1) = channel();
+    let mut computers = vec![
+        Computer::with_io(parse(DUET), Channel::new(to_1, from_1)),
+        Computer::with_io(parse(DUET), Channel::new(to_0, from_0)),
+    ];
+    computers[1].set_register(register('p'), 1);
+
+    let report = Scheduler::new(computers).run();
+    assert_eq!(report.termination, Termination::Deadlock);
+    assert_eq!(report.sent, [3, 3]);
+}
+
+#[test]
+fn starved() {
+    assert_eq!(
+        run(["rcv a", "set a 1"]),
+        (Termination::Starved, vec![None, None])
+    );
+    let (termination, errors) = run(["rcv a", "mod a 0"]);
+    assert_eq!(termination, Termination::Starved);
+    assert_eq!(errors, [None, Some(ExecError::DivisionByZero)]);
+}
+
+#[test]
+fn error() {
+    assert_eq!(
+        run(["set a 1", "mod a 0"]),
+        (
+            Termination::Error,
+            vec![None, Some(ExecError::DivisionByZero)]
+        )
+    );
+}
@@ -0,0 +1,87 @@
+use advent_of_code_2017::intcode::arithmetic::{ArithmeticMode, ModuloMode};
+use advent_of_code_2017::intcode::io::Queue;
+use advent_of_code_2017::intcode::snapshot::Snapshot;
+use advent_of_code_2017::intcode::{Computer, Semantics};
+
+mod common;
+
+use common::{parse, register, DUET, SOUND};
+
+fn round_trip(snapshot: &Snapshot) {
+    let text = snapshot.to_string();
+    assert_eq!(text.parse::<Snapshot>().as_ref(), Ok(snapshot), "{}", text);
+}
+
+#[test]
+fn duet_round_trip() {
+    let mut computer = Computer::with_io(parse(DUET), Queue::new([7, -2, 5]));
+    computer.set_register(register('p'), 1);
+    computer.run_until_halt(Some(5));
+    let snapshot = computer.snapshot().unwrap();
+    assert_eq!(snapshot.sent, 3);
+    assert_eq!(snapshot.input, [5]);
+    round_trip(&snapshot);
+}
+
+#[test]
+fn sound_round_trip() {
+    let mut computer = Computer::with_io(parse(SOUND), Queue::default());
+    computer.set_semantics(Semantics::Sound);
+    computer.set_arithmetic_mode(ArithmeticMode::Wrapping);
+    computer.set_modulo_mode(ModuloMode::Euclidean);
+    computer.run_until_halt(Some(15));
+    let snapshot = computer.snapshot().unwrap();
+    assert_eq!(snapshot.last_played, 4);
+    assert_eq!(snapshot.recovered, Some(4));
+    round_trip(&snapshot);
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn big_round_trip() {
+    let mut computer = Computer::with_io(
+        parse("set a -9223372036854775807\nmul a 10\nset b a\nset c 3"),
+        Queue::default(),
+    );
+    computer.set_arithmetic_mode(ArithmeticMode::Big);
+    computer.run();
+    let snapshot = computer.snapshot().unwrap();
+    assert_eq!(snapshot.big.len(), 2);
+    assert!(snapshot
+        .to_string()
+        .contains("register b -92233720368547758070\n"));
+    round_trip(&snapshot);
+}
+
+#[test]
+fn malformed_snapshots() {
+    let valid = Snapshot::default().to_string();
+    assert_eq!(valid.parse(), Ok(Snapshot::default()));
+
+    for (text, error) in [
+        (
+            valid.replace("v1", "v2"),
+            "Unsupported version: intcode snapshot v2",
+        ),
+        (valid.replace("intcode snapshot v1\n", ""), "Missing header"),
+        (
+            valid.clone() + "register a 1\nregister a 2\n",
+            "Duplicate register: register a 2",
+        ),
+        (valid.clone() + "speed 3\n", "Unknown key: speed 3"),
+        (
+            valid.replace("pc 0", "pc -1"),
+            "invalid digit found in string: pc -1",
+        ),
+        (valid.replace("sent 0", "sent"), "Missing value: sent"),
+        (valid.replace("pc 0", "pc 0 1"), "Trailing data: pc 0 1"),
+    ] {
+        let result = text.parse::<Snapshot>();
+        assert!(
+            result.as_ref().is_err_and(|e| e.starts_with(error)),
+            "{:?} for\n{}",
+            result,
+            text
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use advent_of_code_2017::intcode::assembler::assemble_with;
+use advent_of_code_2017::intcode::dialect::{Dialect, DIV};
+use advent_of_code_2017::intcode::io::NoIo;
+use advent_of_code_2017::intcode::optimizer::optimize;
+use advent_of_code_2017::intcode::trace::{diff, diff_effects, Record};
+use advent_of_code_2017::intcode::{Computer, Instruction, Value};
+
+mod common;
+
+use common::{parse, register, PRIME_SEARCH};
+
+fn trace(
+    instructions: Vec<Instruction>,
+    registers: &[(char, Value)],
+    step_limit: usize,
+) -> Vec<Record> {
+    let mut computer = Computer::with_io(instructions, NoIo);
+    for &(name, value) in registers {
+        computer.set_register(register(name), value);
+    }
+    computer.enable_tracing();
+    computer.run_until_halt(Some(step_limit));
+    computer.take_trace()
+}
+
+#[test]
+fn superinstructions_that_cannot_run() {
+    // The counter is negative, so `mac` runs the original loop instead.
+    let program = "add a b\nsub c 1\njnz c -2";
+    let optimized = optimize(&parse(program));
+    let trace = trace(optimized, &[('b', 1), ('c', -1)], 10);
+    assert_eq!(trace[0].to_string(), "0 0: add a b | a=1");
+    assert!(!trace[0].fused);
+
+    let trace = self::trace(optimize(&parse(program)), &[('b', 2), ('c', 3)], 10);
+    assert_eq!(trace.len(), 1);
+    assert_eq!(trace[0].to_string(), "0 0: mac a b c (fused) | a=6 c=0");
+    assert!(trace[0].fused);
+    assert_eq!(trace[0].to_string().parse(), Ok(trace[0].clone()));
+}
+
+#[test]
+fn extra_opcodes_are_not_fused() {
+    let program = assemble_with("div a 3\nadd b 1", &Dialect::strict().with(&DIV)).unwrap();
+    let trace = trace(program, &[('a', 7)], 10);
+    assert_eq!(trace[0].to_string(), "0 0: div a 3 | a=2");
+    assert!(!trace[0].fused);
+    assert_eq!(trace[0].to_string().parse(), Ok(trace[0].clone()));
+
+    // So their effects are compared one record at a time.
+    let same = self::trace(parse("set a 2\nadd b 1"), &[], 10);
+    assert_eq!(diff_effects(&trace, &same), None);
+    let other = self::trace(parse("set a 2\nadd b 2"), &[], 10);
+    let divergence = diff_effects(&trace, &other).expect("`b` is different.");
+    assert_eq!((divergence.index, divergence.right_index), (1, 1));
+}
+
+#[test]
+fn optimized_prime_search() {
+    let original = trace(parse(PRIME_SEARCH), &[], 1_000_000);
+    let optimized = trace(optimize(&parse(PRIME_SEARCH)), &[], 1_000_000);
+    assert!(optimized.len() < original.len() / 100);
+
+    let divergence = diff(&original, &optimized).expect("The traces are different.");
+    assert_eq!(divergence.fields(), ["instruction", "writes"]);
+    assert_eq!(diff_effects(&original, &optimized), None);
+    assert_eq!(diff_effects(&optimized, &original), None);
+}
+
+#[test]
+fn different_effects() {
+    let left = trace(parse(PRIME_SEARCH), &[], 1_000_000);
+    // The first time `h` is incremented.
+    let program = PRIME_SEARCH.replace("sub h -1", "sub h -2");
+    let right = trace(optimize(&parse(&program)), &[], 1_000_000);
+
+    let divergence = diff_effects(&left, &right).expect("The effects are different.");
+    assert_eq!(divergence.left.unwrap().instruction, "sub h -1");
+    assert_eq!(divergence.right.unwrap().instruction, "sub h -2");
+    assert!(divergence.index > divergence.right_index);
+}